@@ -0,0 +1,64 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use regorus::*;
+
+// Validates thousands of documents against the same schema. Compiled schemas are cached,
+// so only the first call pays for compiling the schema.
+const REPEATED_SCHEMA: &str = r#"
+package bench
+import rego.v1
+
+schema := {
+  "type": "object",
+  "properties": {
+    "id": {"type": "integer", "minimum": 0},
+    "name": {"type": "string", "pattern": "^item-[0-9]+$"},
+    "tags": {"type": "array", "items": {"type": "string"}},
+  },
+  "required": ["id", "name"],
+}
+
+matches := count([i |
+  some i in numbers.range(1, 2000)
+  json.match_schema({"id": i, "name": sprintf("item-%d", [i]), "tags": ["a"]}, schema)[0]
+])
+"#;
+
+// Each call uses a distinct schema and hence must compile it.
+const UNIQUE_SCHEMAS: &str = r#"
+package bench
+import rego.v1
+
+matches := count([i |
+  some i in numbers.range(1, 2000)
+  json.match_schema({"id": i}, {"type": "object", "properties": {"id": {"maximum": i}}})[0]
+])
+"#;
+
+fn eval(c: &mut Criterion, name: &str, policy: &str) {
+    let mut engine = Engine::new();
+    engine
+        .add_policy("bench.rego".to_string(), policy.to_string())
+        .unwrap();
+
+    c.bench_function(name, |b| {
+        b.iter(|| {
+            let matches = engine.eval_rule("data.bench.matches".to_string()).unwrap();
+            assert_eq!(matches, Value::from(2000u64));
+        })
+    });
+}
+
+fn json_match_schema(c: &mut Criterion) {
+    eval(c, "json.match_schema repeated schema", REPEATED_SCHEMA);
+    eval(c, "json.match_schema unique schemas", UNIQUE_SCHEMAS);
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = json_match_schema
+}
+criterion_main!(benches);
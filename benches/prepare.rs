@@ -0,0 +1,53 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use regorus::*;
+
+const POLICIES: [&str; 3] = [
+    "tests/aci/framework.rego",
+    "tests/aci/api.rego",
+    "tests/aci/policy.rego",
+];
+
+const QUERY: &str = "data.framework.mount_overlay.allowed";
+
+fn load_engine() -> Engine {
+    let mut engine = Engine::new();
+    for policy in POLICIES {
+        engine.add_policy_from_file(policy).unwrap();
+    }
+    engine
+        .add_data(Value::from_json_file("tests/aci/data.json").unwrap())
+        .unwrap();
+    engine
+}
+
+fn evaluate(engine: &mut Engine, input: &Value) {
+    engine.set_input(input.clone());
+    let results = engine.eval_query(QUERY.to_string(), false).unwrap();
+    assert_eq!(results.result[0].expressions[0].value, Value::from(true));
+}
+
+fn prepare(c: &mut Criterion) {
+    let input = Value::from_json_file("tests/aci/input.json").unwrap();
+
+    // Parse and analyze the policies for every request.
+    c.bench_function("parse per request", |b| {
+        b.iter(|| evaluate(&mut load_engine(), &input))
+    });
+
+    // Parse and analyze once; clone the prepared engine for every request.
+    let mut prepared = load_engine();
+    prepared.prepare().unwrap();
+    c.bench_function("prepared engine reuse", |b| {
+        b.iter(|| evaluate(&mut prepared.clone(), &input))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = prepare
+}
+criterion_main!(benches);
@@ -0,0 +1,54 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use regorus::*;
+
+// Matches thousands of strings against the same pattern. Compiled patterns are cached,
+// so only the first call pays for compiling the regex.
+const REPEATED_PATTERN: &str = r#"
+package bench
+import rego.v1
+
+matches := count([i |
+  some i in numbers.range(1, 5000)
+  regex.match(`^item-[0-9]+$`, sprintf("item-%d", [i]))
+])
+"#;
+
+// Each call uses a distinct pattern and hence must compile it.
+const UNIQUE_PATTERNS: &str = r#"
+package bench
+import rego.v1
+
+matches := count([i |
+  some i in numbers.range(1, 5000)
+  regex.match(sprintf(`^item-%d$`, [i]), sprintf("item-%d", [i]))
+])
+"#;
+
+fn eval(c: &mut Criterion, name: &str, policy: &str) {
+    let mut engine = Engine::new();
+    engine
+        .add_policy("bench.rego".to_string(), policy.to_string())
+        .unwrap();
+
+    c.bench_function(name, |b| {
+        b.iter(|| {
+            let matches = engine.eval_rule("data.bench.matches".to_string()).unwrap();
+            assert_eq!(matches, Value::from(5000u64));
+        })
+    });
+}
+
+fn regex_match(c: &mut Criterion) {
+    eval(c, "regex.match repeated pattern", REPEATED_PATTERN);
+    eval(c, "regex.match unique patterns", UNIQUE_PATTERNS);
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = regex_match
+}
+criterion_main!(benches);
@@ -0,0 +1,58 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+// Registers a custom `mycorp.lookup` function and calls it from a policy.
+//
+// $ cargo run --example extension
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use regorus::{Engine, Value};
+
+fn main() -> Result<()> {
+    let mut engine = Engine::new();
+    engine.add_policy(
+        "repos.rego".to_string(),
+        r#"package repos
+import rego.v1
+
+# Owner of the repository being accessed, looked up via the extension.
+owner := mycorp.lookup(input.repo)
+
+allow if owner == input.user
+"#
+        .to_string(),
+    )?;
+
+    // The owners would typically come from a service or database owned by the embedder.
+    let owners = BTreeMap::from([("repo-a", "alice"), ("repo-b", "bob")]);
+
+    // The extension receives the evaluated arguments. Returning Undefined makes the
+    // calling expression undefined, just like a builtin. Errors are reported with the
+    // location of the call.
+    engine.add_extension(
+        "mycorp.lookup".to_string(),
+        1,
+        Box::new(move |params: Vec<Value>| {
+            let repo = params[0].as_string()?;
+            Ok(owners
+                .get(repo.as_ref())
+                .map_or(Value::Undefined, |owner| Value::from(*owner)))
+        }),
+    )?;
+
+    for (user, repo) in [
+        ("alice", "repo-a"),
+        ("alice", "repo-b"),
+        ("carol", "repo-c"),
+    ] {
+        engine.set_input(Value::from_json_str(&format!(
+            r#"{{ "user": "{user}", "repo": "{repo}" }}"#
+        ))?);
+        let allow = engine.eval_rule("data.repos.allow".to_string())?;
+        println!("{user} -> {repo}: {}", allow == Value::from(true));
+    }
+
+    Ok(())
+}
@@ -336,6 +336,29 @@ pub enum RuleHead {
     },
 }
 
+/// A `# METADATA` comment block immediately preceding a package or rule.
+///
+/// Parsing annotations requires the `yaml` feature. Without it, policies containing
+/// annotations are rejected.
+#[derive(Debug)]
+#[cfg_attr(feature = "ast", derive(serde::Serialize))]
+pub struct Annotation {
+    /// Span of the `# METADATA` line.
+    pub span: Span,
+    /// YAML contents of the block with the leading `#` of each line removed.
+    pub yaml: String,
+    /// One of `rule` or `document` for rules and `package` or `subpackages` for packages.
+    /// Defaults to `rule` and `package` respectively.
+    pub scope: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// The `custom` object; [`Value::Undefined`] if absent.
+    pub custom: Value,
+    /// The annotation as returned by `rego.metadata.rule`, with the scope made explicit and
+    /// authors and related resources normalized to objects.
+    pub value: Value,
+}
+
 #[derive(Debug)]
 #[cfg_attr(feature = "ast", derive(serde::Serialize))]
 pub enum Rule {
@@ -343,6 +366,8 @@ pub enum Rule {
         span: Span,
         head: RuleHead,
         bodies: Vec<RuleBody>,
+        #[cfg_attr(feature = "ast", serde(skip_serializing_if = "Option::is_none"))]
+        annotation: Option<Ref<Annotation>>,
     },
     Default {
         span: Span,
@@ -350,6 +375,8 @@ pub enum Rule {
         args: Vec<Ref<Expr>>,
         op: AssignOp,
         value: Ref<Expr>,
+        #[cfg_attr(feature = "ast", serde(skip_serializing_if = "Option::is_none"))]
+        annotation: Option<Ref<Annotation>>,
     },
 }
 
@@ -359,6 +386,12 @@ impl Rule {
             Self::Spec { span, .. } | Self::Default { span, .. } => span,
         }
     }
+
+    pub fn annotation(&self) -> Option<&Ref<Annotation>> {
+        match self {
+            Self::Spec { annotation, .. } | Self::Default { annotation, .. } => annotation.as_ref(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -366,6 +399,8 @@ impl Rule {
 pub struct Package {
     pub span: Span,
     pub refr: Ref<Expr>,
+    #[cfg_attr(feature = "ast", serde(skip_serializing_if = "Option::is_none"))]
+    pub annotation: Option<Ref<Annotation>>,
 }
 
 #[derive(Debug)]
@@ -6,9 +6,11 @@ use crate::builtins;
 use crate::builtins::utils::{ensure_args_count, ensure_numeric};
 
 use crate::lexer::Span;
+use crate::number::Number;
 use crate::value::Value;
+use crate::*;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 
 pub fn register(m: &mut builtins::BuiltinsMap<&'static str, builtins::BuiltinFcn>) {
     m.insert("bits.and", (and, 2));
@@ -19,12 +21,32 @@ pub fn register(m: &mut builtins::BuiltinsMap<&'static str, builtins::BuiltinFcn
     m.insert("bits.xor", (xor, 2));
 }
 
+fn ensure_integer(name: &str, param: &Expr, arg: &Value) -> Result<Number> {
+    let n = ensure_numeric(name, param, arg)?;
+    if !n.is_integer() {
+        bail!(param.span().error(&format!(
+            "`{name}` expects integer argument. Got `{arg}` instead"
+        )));
+    }
+    Ok(n)
+}
+
+fn ensure_shift_count(name: &str, param: &Expr, arg: &Value) -> Result<Number> {
+    let n = ensure_integer(name, param, arg)?;
+    if n < Number::from(0u64) {
+        bail!(param.span().error(&format!(
+            "`{name}` expects non-negative shift count. Got `{arg}` instead"
+        )));
+    }
+    Ok(n)
+}
+
 fn and(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Result<Value> {
     let name = "bits.and";
     ensure_args_count(span, name, params, args, 2)?;
 
-    let v1 = ensure_numeric(name, &params[0], &args[0])?;
-    let v2 = ensure_numeric(name, &params[1], &args[1])?;
+    let v1 = ensure_integer(name, &params[0], &args[0])?;
+    let v2 = ensure_integer(name, &params[1], &args[1])?;
 
     Ok(match v1.and(&v2) {
         Some(v) => Value::from(v),
@@ -36,8 +58,8 @@ fn lsh(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Resu
     let name = "bits.lsh";
     ensure_args_count(span, name, params, args, 2)?;
 
-    let v1 = ensure_numeric(name, &params[0], &args[0])?;
-    let v2 = ensure_numeric(name, &params[1], &args[1])?;
+    let v1 = ensure_integer(name, &params[0], &args[0])?;
+    let v2 = ensure_shift_count(name, &params[1], &args[1])?;
 
     Ok(match v1.lsh(&v2) {
         Some(v) => Value::from(v),
@@ -49,7 +71,7 @@ fn negate(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> R
     let name = "bits.negate";
     ensure_args_count(span, name, params, args, 1)?;
 
-    let v = ensure_numeric(name, &params[0], &args[0])?;
+    let v = ensure_integer(name, &params[0], &args[0])?;
 
     Ok(match v.neg() {
         Some(v) => Value::from(v),
@@ -61,8 +83,8 @@ fn or(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Resul
     let name = "bits.or";
     ensure_args_count(span, name, params, args, 2)?;
 
-    let v1 = ensure_numeric(name, &params[0], &args[0])?;
-    let v2 = ensure_numeric(name, &params[1], &args[1])?;
+    let v1 = ensure_integer(name, &params[0], &args[0])?;
+    let v2 = ensure_integer(name, &params[1], &args[1])?;
 
     Ok(match v1.or(&v2) {
         Some(v) => Value::from(v),
@@ -74,8 +96,8 @@ fn rsh(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Resu
     let name = "bits.rsh";
     ensure_args_count(span, name, params, args, 2)?;
 
-    let v1 = ensure_numeric(name, &params[0], &args[0])?;
-    let v2 = ensure_numeric(name, &params[1], &args[1])?;
+    let v1 = ensure_integer(name, &params[0], &args[0])?;
+    let v2 = ensure_shift_count(name, &params[1], &args[1])?;
 
     Ok(match v1.rsh(&v2) {
         Some(v) => Value::from(v),
@@ -87,8 +109,8 @@ fn xor(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Resu
     let name = "bits.xor";
     ensure_args_count(span, name, params, args, 2)?;
 
-    let v1 = ensure_numeric(name, &params[0], &args[0])?;
-    let v2 = ensure_numeric(name, &params[1], &args[1])?;
+    let v1 = ensure_integer(name, &params[0], &args[0])?;
+    let v2 = ensure_integer(name, &params[1], &args[1])?;
 
     Ok(match v1.xor(&v2) {
         Some(v) => Value::from(v),
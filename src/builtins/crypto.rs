@@ -14,6 +14,9 @@ use md5::{Digest, Md5};
 use sha1::Sha1;
 use sha2::{Sha256, Sha512};
 
+#[cfg(feature = "x509")]
+mod x509;
+
 pub fn register(m: &mut builtins::BuiltinsMap<&'static str, builtins::BuiltinFcn>) {
     m.insert("crypto.hmac.equal", (hmac_equal_fixed_time, 2));
     m.insert("crypto.hmac.md5", (hmac_md5, 2));
@@ -24,6 +27,20 @@ pub fn register(m: &mut builtins::BuiltinsMap<&'static str, builtins::BuiltinFcn
     m.insert("crypto.md5", (crypto_md5, 1));
     m.insert("crypto.sha1", (crypto_sha1, 1));
     m.insert("crypto.sha256", (crypto_sha256, 1));
+
+    m.insert("value.hash", (value_hash, 1));
+
+    #[cfg(feature = "x509")]
+    {
+        m.insert(
+            "crypto.x509.parse_certificate_request",
+            (x509::parse_certificate_request, 1),
+        );
+        m.insert(
+            "crypto.x509.parse_certificates",
+            (x509::parse_certificates, 1),
+        );
+    }
 }
 
 fn hmac_equal_fixed_time(
@@ -38,6 +55,9 @@ fn hmac_equal_fixed_time(
     let hmac1 = ensure_string(name, &params[0], &args[0])?;
     let hmac2 = ensure_string(name, &params[1], &args[1])?;
 
+    // The contents are compared in constant time. Like Go's hmac.Equal, strings of
+    // different lengths are unequal without comparing contents since the length of a
+    // mac is not secret.
     Ok(Value::Bool(constant_time_eq(
         hmac1.as_bytes(),
         hmac2.as_bytes(),
@@ -154,3 +174,62 @@ fn crypto_sha256(
 
     Ok(Value::String(hex::encode(result).into()))
 }
+
+// Feeds an unambiguous encoding of the value to the hasher. Each value is tagged with its
+// type and containers are prefixed with their length, so that e.g. a set and an array with
+// the same elements, or an object with key 1 and one with key "1", hash differently.
+// Objects and sets are ordered, so equal values are always encoded identically.
+fn hash_value(h: &mut Sha256, v: &Value) -> Result<()> {
+    match v {
+        Value::Null => h.update(b"n"),
+        Value::Bool(true) => h.update(b"t"),
+        Value::Bool(false) => h.update(b"f"),
+        Value::Number(n) => {
+            // Hash the exact decimal. Serializing via serde_json would round to f64.
+            let n = n.format_decimal();
+            h.update(b"d");
+            h.update((n.len() as u64).to_le_bytes());
+            h.update(n.as_bytes());
+        }
+        Value::String(s) => {
+            h.update(b"s");
+            h.update((s.len() as u64).to_le_bytes());
+            h.update(s.as_bytes());
+        }
+        Value::Array(a) => {
+            h.update(b"a");
+            h.update((a.len() as u64).to_le_bytes());
+            for e in a.iter() {
+                hash_value(h, e)?;
+            }
+        }
+        Value::Set(s) => {
+            h.update(b"S");
+            h.update((s.len() as u64).to_le_bytes());
+            for e in s.iter() {
+                hash_value(h, e)?;
+            }
+        }
+        Value::Object(o) => {
+            h.update(b"o");
+            h.update((o.len() as u64).to_le_bytes());
+            for (k, v) in o.iter() {
+                hash_value(h, k)?;
+                hash_value(h, v)?;
+            }
+        }
+        Value::Undefined => bail!("cannot hash undefined value"),
+    }
+    Ok(())
+}
+
+fn value_hash(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Result<Value> {
+    let name = "value.hash";
+    ensure_args_count(span, name, params, args, 1)?;
+
+    let mut h = Sha256::new();
+    hash_value(&mut h, &args[0])?;
+    let result = h.finalize();
+
+    Ok(Value::String(hex::encode(result).into()))
+}
@@ -0,0 +1,415 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+// The values returned by the x509 builtins follow the json representation of the
+// corresponding Go types (x509.Certificate, pkix.Name etc.) produced by OPA.
+// Enumerations like SignatureAlgorithm are returned as Go's integer values and
+// absent lists are null.
+
+use crate::ast::{Expr, Ref};
+use crate::builtins::utils::{ensure_args_count, ensure_string};
+use crate::lexer::Span;
+use crate::number::Number;
+use crate::value::Value;
+use crate::*;
+
+use core::str::FromStr;
+
+use anyhow::{bail, Result};
+use x509_parser::certification_request::X509CertificationRequest;
+use x509_parser::extensions::{GeneralName, ParsedExtension};
+use x509_parser::oid_registry::Oid;
+use x509_parser::pem::Pem;
+use x509_parser::prelude::{FromDer, X509Certificate};
+use x509_parser::signature_algorithm::SignatureAlgorithm;
+use x509_parser::time::ASN1Time;
+use x509_parser::x509::{AlgorithmIdentifier, X509Name};
+
+fn object(fields: Vec<(&str, Value)>) -> Value {
+    Value::from_map(
+        fields
+            .into_iter()
+            .map(|(k, v)| (Value::from(k), v))
+            .collect(),
+    )
+}
+
+fn strings_or_null(strings: Vec<String>) -> Value {
+    if strings.is_empty() {
+        Value::Null
+    } else {
+        Value::from_array(strings.into_iter().map(Value::from).collect())
+    }
+}
+
+fn bytes_or_null(bytes: Option<&[u8]>) -> Value {
+    match bytes {
+        Some(bytes) => Value::from(data_encoding::BASE64.encode(bytes)),
+        None => Value::Null,
+    }
+}
+
+// Decode the argument which may be PEM, base64 encoded PEM or base64 encoded DER.
+fn decode_input(param: &Expr, input: &str) -> Result<Vec<u8>> {
+    let input = input.trim();
+    if input.starts_with("-----BEGIN") {
+        return Ok(input.as_bytes().to_vec());
+    }
+    let mut encoded: Vec<u8> = input.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    // Accept unpadded base64 too.
+    let padding = (4 - encoded.len() % 4) % 4;
+    encoded.resize(encoded.len() + padding, b'=');
+    data_encoding::BASE64.decode(&encoded).map_err(|e| {
+        param
+            .span()
+            .error(&format!("input is neither PEM nor base64\nCaused by\n{e}"))
+    })
+}
+
+// Extract the DER encoded contents of PEM blocks with one of the given labels.
+// Returns None if the bytes are not PEM encoded.
+fn pem_contents(param: &Expr, bytes: &[u8], labels: &[&str]) -> Result<Option<Vec<Vec<u8>>>> {
+    let start = bytes
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    if !bytes[start..].starts_with(b"-----BEGIN") {
+        return Ok(None);
+    }
+    let mut contents = vec![];
+    for pem in Pem::iter_from_buffer(bytes) {
+        let pem = pem.map_err(|e| {
+            param
+                .span()
+                .error(&format!("invalid PEM block\nCaused by\n{e}"))
+        })?;
+        if labels.contains(&pem.label.as_str()) {
+            contents.push(pem.contents);
+        }
+    }
+    if contents.is_empty() {
+        bail!(param
+            .span()
+            .error(&format!("no {} PEM block found", labels[0])));
+    }
+    Ok(Some(contents))
+}
+
+fn oid_to_value(oid: &Oid) -> Value {
+    match oid.iter() {
+        Some(arcs) => Value::from_array(arcs.map(Value::from).collect()),
+        None => Value::from(oid.to_id_string()),
+    }
+}
+
+fn name_to_value(name: &X509Name) -> Value {
+    let attributes = |oid: &str| -> Vec<String> {
+        name.iter_attributes()
+            .filter(|a| a.attr_type().to_id_string() == oid)
+            .filter_map(|a| a.as_str().ok().map(|s| s.to_string()))
+            .collect()
+    };
+    let attribute = |oid: &str| -> Value {
+        Value::from(attributes(oid).into_iter().next().unwrap_or_default())
+    };
+
+    let names = name
+        .iter_attributes()
+        .map(|a| {
+            object(vec![
+                ("Type", oid_to_value(a.attr_type())),
+                (
+                    "Value",
+                    match a.as_str() {
+                        Ok(s) => Value::from(s),
+                        Err(_) => Value::Null,
+                    },
+                ),
+            ])
+        })
+        .collect();
+
+    object(vec![
+        ("CommonName", attribute("2.5.4.3")),
+        ("Country", strings_or_null(attributes("2.5.4.6"))),
+        ("ExtraNames", Value::Null),
+        ("Locality", strings_or_null(attributes("2.5.4.7"))),
+        ("Names", Value::from_array(names)),
+        ("Organization", strings_or_null(attributes("2.5.4.10"))),
+        (
+            "OrganizationalUnit",
+            strings_or_null(attributes("2.5.4.11")),
+        ),
+        ("PostalCode", strings_or_null(attributes("2.5.4.17"))),
+        ("Province", strings_or_null(attributes("2.5.4.8"))),
+        ("SerialNumber", attribute("2.5.4.5")),
+        ("StreetAddress", strings_or_null(attributes("2.5.4.9"))),
+    ])
+}
+
+fn time_to_value(time: ASN1Time) -> Value {
+    let t = time.to_datetime();
+    Value::from(format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        t.year(),
+        t.month() as u8,
+        t.day(),
+        t.hour(),
+        t.minute(),
+        t.second()
+    ))
+}
+
+// Go's x509.SignatureAlgorithm.
+fn signature_algorithm(alg: &AlgorithmIdentifier) -> Value {
+    let id = match alg.algorithm.to_id_string().as_str() {
+        "1.2.840.113549.1.1.2" => 1,
+        "1.2.840.113549.1.1.4" => 2,
+        "1.2.840.113549.1.1.5" => 3,
+        "1.2.840.113549.1.1.11" => 4,
+        "1.2.840.113549.1.1.12" => 5,
+        "1.2.840.113549.1.1.13" => 6,
+        "1.2.840.10040.4.3" => 7,
+        "2.16.840.1.101.3.4.3.2" => 8,
+        "1.2.840.10045.4.1" => 9,
+        "1.2.840.10045.4.3.2" => 10,
+        "1.2.840.10045.4.3.3" => 11,
+        "1.2.840.10045.4.3.4" => 12,
+        "1.2.840.113549.1.1.10" => match SignatureAlgorithm::try_from(alg) {
+            Ok(SignatureAlgorithm::RSASSA_PSS(params)) => {
+                match params.hash_algorithm_oid().to_id_string().as_str() {
+                    "2.16.840.1.101.3.4.2.1" => 13,
+                    "2.16.840.1.101.3.4.2.2" => 14,
+                    "2.16.840.1.101.3.4.2.3" => 15,
+                    _ => 0,
+                }
+            }
+            _ => 0,
+        },
+        "1.3.101.112" => 16,
+        _ => 0,
+    };
+    Value::from(id as u64)
+}
+
+// Go's x509.PublicKeyAlgorithm.
+fn public_key_algorithm(alg: &AlgorithmIdentifier) -> Value {
+    let id = match alg.algorithm.to_id_string().as_str() {
+        "1.2.840.113549.1.1.1" => 1,
+        "1.2.840.10040.4.1" => 2,
+        "1.2.840.10045.2.1" => 3,
+        "1.3.101.112" => 4,
+        _ => 0,
+    };
+    Value::from(id as u64)
+}
+
+// Subject alternative names grouped as DNSNames, EmailAddresses, IPAddresses and URIs.
+fn alternative_names<'a>(
+    names: impl Iterator<Item = &'a GeneralName<'a>>,
+) -> [(&'static str, Value); 4] {
+    let (mut dns, mut emails, mut ips, mut uris) = (vec![], vec![], vec![], vec![]);
+    for name in names {
+        match name {
+            GeneralName::DNSName(s) => dns.push(s.to_string()),
+            GeneralName::RFC822Name(s) => emails.push(s.to_string()),
+            GeneralName::URI(s) => uris.push(s.to_string()),
+            GeneralName::IPAddress(b) => {
+                if let Ok(b) = <[u8; 4]>::try_from(*b) {
+                    ips.push(core::net::Ipv4Addr::from(b).to_string());
+                } else if let Ok(b) = <[u8; 16]>::try_from(*b) {
+                    ips.push(core::net::Ipv6Addr::from(b).to_string());
+                }
+            }
+            _ => (),
+        }
+    }
+    [
+        ("DNSNames", strings_or_null(dns)),
+        ("EmailAddresses", strings_or_null(emails)),
+        ("IPAddresses", strings_or_null(ips)),
+        ("URIs", strings_or_null(uris)),
+    ]
+}
+
+fn certificate_to_value(raw: &[u8], cert: &X509Certificate) -> Result<Value> {
+    let serial = Number::from_str(&cert.tbs_certificate.serial.to_string())
+        .map_err(|_| anyhow::anyhow!("invalid serial number"))?;
+
+    let basic_constraints = cert.basic_constraints().ok().flatten();
+    let max_path_len = match &basic_constraints {
+        Some(bc) => match bc.value.path_len_constraint {
+            Some(len) => Value::from(len as u64),
+            None => Value::from(-1i64),
+        },
+        None => Value::from(0u64),
+    };
+
+    let ext_key_usage = match cert.extended_key_usage().ok().flatten() {
+        Some(eku) => {
+            let eku = eku.value;
+            let usages: Vec<Value> = [
+                (eku.any, 0u64),
+                (eku.server_auth, 1),
+                (eku.client_auth, 2),
+                (eku.code_signing, 3),
+                (eku.email_protection, 4),
+                (eku.time_stamping, 8),
+                (eku.ocsp_signing, 9),
+            ]
+            .into_iter()
+            .filter(|(present, _)| *present)
+            .map(|(_, usage)| Value::from(usage))
+            .collect();
+            Value::from_array(usages)
+        }
+        None => Value::Null,
+    };
+
+    let subject_key_id = cert
+        .iter_extensions()
+        .find_map(|e| match e.parsed_extension() {
+            ParsedExtension::SubjectKeyIdentifier(id) => Some(id.0),
+            _ => None,
+        });
+    let authority_key_id = cert
+        .iter_extensions()
+        .find_map(|e| match e.parsed_extension() {
+            ParsedExtension::AuthorityKeyIdentifier(aki) => {
+                aki.key_identifier.as_ref().map(|id| id.0)
+            }
+            _ => None,
+        });
+
+    let san = cert.subject_alternative_name().ok().flatten();
+    let mut fields = vec![
+        ("AuthorityKeyId", bytes_or_null(authority_key_id)),
+        (
+            "BasicConstraintsValid",
+            Value::from(basic_constraints.is_some()),
+        ),
+        ("ExtKeyUsage", ext_key_usage),
+        ("IsCA", Value::from(cert.is_ca())),
+        ("Issuer", name_to_value(cert.issuer())),
+        (
+            "KeyUsage",
+            Value::from(match cert.key_usage().ok().flatten() {
+                Some(ku) => ku.value.flags as u64,
+                None => 0,
+            }),
+        ),
+        ("MaxPathLen", max_path_len),
+        ("NotAfter", time_to_value(cert.validity().not_after)),
+        ("NotBefore", time_to_value(cert.validity().not_before)),
+        (
+            "PublicKeyAlgorithm",
+            public_key_algorithm(&cert.public_key().algorithm),
+        ),
+        ("Raw", Value::from(data_encoding::BASE64.encode(raw))),
+        ("SerialNumber", Value::from(serial)),
+        (
+            "SignatureAlgorithm",
+            signature_algorithm(&cert.signature_algorithm),
+        ),
+        ("Subject", name_to_value(cert.subject())),
+        ("SubjectKeyId", bytes_or_null(subject_key_id)),
+        ("Version", Value::from(cert.version().0 as u64 + 1)),
+    ];
+    fields.extend(alternative_names(
+        san.iter().flat_map(|san| san.value.general_names.iter()),
+    ));
+
+    Ok(object(fields))
+}
+
+pub fn parse_certificates(
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+    _strict: bool,
+) -> Result<Value> {
+    let name = "crypto.x509.parse_certificates";
+    ensure_args_count(span, name, params, args, 1)?;
+
+    let input = ensure_string(name, &params[0], &args[0])?;
+    let bytes = decode_input(&params[0], &input)?;
+
+    let ders = match pem_contents(&params[0], &bytes, &["CERTIFICATE"])? {
+        Some(ders) => ders,
+        None => vec![bytes],
+    };
+
+    let mut certs = vec![];
+    for der in &ders {
+        // DER input may contain multiple concatenated certificates.
+        let mut remaining = der.as_slice();
+        while !remaining.is_empty() {
+            let (rest, cert) = X509Certificate::from_der(remaining).map_err(|e| {
+                params[0]
+                    .span()
+                    .error(&format!("could not parse certificate\nCaused by\n{e}"))
+            })?;
+            let raw = &remaining[..remaining.len() - rest.len()];
+            certs.push(certificate_to_value(raw, &cert)?);
+            remaining = rest;
+        }
+    }
+
+    Ok(Value::from_array(certs))
+}
+
+pub fn parse_certificate_request(
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+    _strict: bool,
+) -> Result<Value> {
+    let name = "crypto.x509.parse_certificate_request";
+    ensure_args_count(span, name, params, args, 1)?;
+
+    let input = ensure_string(name, &params[0], &args[0])?;
+    let bytes = decode_input(&params[0], &input)?;
+
+    let der = match pem_contents(
+        &params[0],
+        &bytes,
+        &["CERTIFICATE REQUEST", "NEW CERTIFICATE REQUEST"],
+    )? {
+        Some(mut ders) => ders.remove(0),
+        None => bytes,
+    };
+
+    let (_, csr) = X509CertificationRequest::from_der(&der).map_err(|e| {
+        params[0].span().error(&format!(
+            "could not parse certificate request\nCaused by\n{e}"
+        ))
+    })?;
+    let info = &csr.certification_request_info;
+
+    let names = csr
+        .requested_extensions()
+        .into_iter()
+        .flatten()
+        .filter_map(|e| match e {
+            ParsedExtension::SubjectAlternativeName(san) => Some(san.general_names.iter()),
+            _ => None,
+        })
+        .flatten();
+
+    let mut fields = vec![
+        (
+            "PublicKeyAlgorithm",
+            public_key_algorithm(&info.subject_pki.algorithm),
+        ),
+        ("Raw", Value::from(data_encoding::BASE64.encode(&der))),
+        (
+            "SignatureAlgorithm",
+            signature_algorithm(&csr.signature_algorithm),
+        ),
+        ("Subject", name_to_value(&info.subject)),
+        ("Version", Value::from(info.version.0 as u64)),
+    ];
+    fields.extend(alternative_names(names));
+
+    Ok(object(fields))
+}
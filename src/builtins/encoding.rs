@@ -15,6 +15,12 @@ use crate::*;
 use anyhow::{anyhow, bail, Context, Result};
 
 pub fn register(m: &mut builtins::BuiltinsMap<&'static str, builtins::BuiltinFcn>) {
+    #[cfg(feature = "base32")]
+    {
+        m.insert("base32.decode", (base32_decode, 1));
+        m.insert("base32.encode", (base32_encode, 1));
+        m.insert("base32.is_valid", (base32_is_valid, 1));
+    }
     #[cfg(feature = "base64")]
     {
         m.insert("base64.decode", (base64_decode, 1));
@@ -27,10 +33,16 @@ pub fn register(m: &mut builtins::BuiltinsMap<&'static str, builtins::BuiltinFcn
         m.insert("base64url.encode", (base64url_encode, 1));
         m.insert("base64url.encode_no_pad", (base64url_encode_no_pad, 1));
     }
+    #[cfg(feature = "cbor")]
+    {
+        m.insert("cbor.marshal", (cbor_marshal, 1));
+        m.insert("cbor.unmarshal", (cbor_unmarshal, 1));
+    }
     #[cfg(feature = "hex")]
     {
         m.insert("hex.decode", (hex_decode, 1));
         m.insert("hex.encode", (hex_encode, 1));
+        m.insert("hex.encode_upper", (hex_encode_upper, 1));
     }
     #[cfg(feature = "urlquery")]
     {
@@ -42,23 +54,102 @@ pub fn register(m: &mut builtins::BuiltinsMap<&'static str, builtins::BuiltinFcn
     m.insert("json.is_valid", (json_is_valid, 1));
     m.insert("json.marshal", (json_marshal, 1));
     m.insert("json.marshal_with_options", (json_marshal_with_options, 2));
+    m.insert("json.merge_patch", (json_merge_patch, 2));
     m.insert("json.unmarshal", (json_unmarshal, 1));
 
+    #[cfg(feature = "toml")]
+    {
+        m.insert("toml.is_valid", (toml_is_valid, 1));
+        m.insert("toml.marshal", (toml_marshal, 1));
+        m.insert("toml.unmarshal", (toml_unmarshal, 1));
+    }
+
+    #[cfg(feature = "xml")]
+    {
+        m.insert("xml.unmarshal", (xml_unmarshal, 1));
+    }
+
     #[cfg(feature = "yaml")]
     {
         m.insert("yaml.is_valid", (yaml_is_valid, 1));
         m.insert("yaml.marshal", (yaml_marshal, 1));
         m.insert("yaml.unmarshal", (yaml_unmarshal, 1));
+        m.insert("yaml.unmarshal_all", (yaml_unmarshal_all, 1));
+    }
+    #[cfg(feature = "z85")]
+    {
+        m.insert("z85.decode", (z85_decode, 1));
+        m.insert("z85.encode", (z85_encode, 1));
     }
 }
 
-#[cfg(feature = "base64")]
-fn base64_decode(
+// Convert decoded bytes to a string value.
+// In strict mode, bytes that are not valid utf-8 raise an error so that binary payloads
+// are not silently corrupted. Otherwise invalid sequences are replaced lossily.
+#[cfg(any(feature = "base32", feature = "base64", feature = "z85"))]
+fn decoded_bytes_to_value(param: &Expr, bytes: Vec<u8>, strict: bool) -> Result<Value> {
+    if strict {
+        match String::from_utf8(bytes) {
+            Ok(s) => Ok(Value::String(s.into())),
+            Err(e) => bail!(param.span().error(&format!(
+                "decoded bytes are not valid utf-8\nCaused by\n{e}"
+            ))),
+        }
+    } else {
+        Ok(Value::String(String::from_utf8_lossy(&bytes).into()))
+    }
+}
+
+#[cfg(feature = "base32")]
+fn base32_decode(span: &Span, params: &[Ref<Expr>], args: &[Value], strict: bool) -> Result<Value> {
+    let name = "base32.decode";
+    ensure_args_count(span, name, params, args, 1)?;
+
+    let encoded_str = ensure_string(name, &params[0], &args[0])?;
+    let decoded_bytes = data_encoding::BASE32
+        .decode(encoded_str.as_bytes())
+        .map_err(|e| {
+            params[0]
+                .span()
+                .error(&format!("decode failed\nCaused by\n{e}"))
+        })?;
+    decoded_bytes_to_value(&params[0], decoded_bytes, strict)
+}
+
+#[cfg(feature = "base32")]
+fn base32_encode(
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+    _strict: bool,
+) -> Result<Value> {
+    let name = "base32.encode";
+    ensure_args_count(span, name, params, args, 1)?;
+
+    let string = ensure_string(name, &params[0], &args[0])?;
+    Ok(Value::String(
+        data_encoding::BASE32.encode(string.as_bytes()).into(),
+    ))
+}
+
+#[cfg(feature = "base32")]
+fn base32_is_valid(
     span: &Span,
     params: &[Ref<Expr>],
     args: &[Value],
     _strict: bool,
 ) -> Result<Value> {
+    let name = "base32.is_valid";
+    ensure_args_count(span, name, params, args, 1)?;
+
+    let encoded_str = ensure_string(name, &params[0], &args[0])?;
+    Ok(Value::Bool(
+        data_encoding::BASE32.decode(encoded_str.as_bytes()).is_ok(),
+    ))
+}
+
+#[cfg(feature = "base64")]
+fn base64_decode(span: &Span, params: &[Ref<Expr>], args: &[Value], strict: bool) -> Result<Value> {
     let name = "base64.decode";
     ensure_args_count(span, name, params, args, 1)?;
 
@@ -70,9 +161,7 @@ fn base64_decode(
                 .span()
                 .error(&format!("decode failed\nCaused by\n{e}"))
         })?;
-    Ok(Value::String(
-        String::from_utf8_lossy(&decoded_bytes).into(),
-    ))
+    decoded_bytes_to_value(&params[0], decoded_bytes, strict)
 }
 
 #[cfg(feature = "base64")]
@@ -173,6 +262,86 @@ fn base64url_encode_no_pad(
     ))
 }
 
+// Convert a decoded cbor item to a value.
+//
+// - Byte strings are converted to standard base64 encoded strings.
+// - Map keys that are not text strings are converted to their json string representation
+//   (e.g. `1` becomes `"1"`), matching how non-string keys are serialized to json.
+// - Tags are ignored and the tagged item is converted.
+#[cfg(feature = "cbor")]
+fn cbor_to_value(v: ciborium::Value) -> Result<Value> {
+    use ciborium::Value as CborValue;
+    Ok(match v {
+        CborValue::Null => Value::Null,
+        CborValue::Bool(b) => Value::from(b),
+        CborValue::Integer(i) => Value::from(i128::from(i)),
+        CborValue::Float(f) => Value::from(f),
+        CborValue::Text(s) => Value::from(s),
+        CborValue::Bytes(b) => Value::from(data_encoding::BASE64.encode(&b)),
+        CborValue::Tag(_, v) => cbor_to_value(*v)?,
+        CborValue::Array(a) => Value::from(
+            a.into_iter()
+                .map(cbor_to_value)
+                .collect::<Result<Vec<Value>>>()?,
+        ),
+        CborValue::Map(m) => {
+            let mut obj = alloc::collections::BTreeMap::new();
+            for (k, v) in m {
+                let key = match cbor_to_value(k)? {
+                    k @ Value::String(_) => k,
+                    k => Value::from(serde_json::to_string(&k)?),
+                };
+                obj.insert(key, cbor_to_value(v)?);
+            }
+            Value::from(obj)
+        }
+        _ => bail!("unsupported cbor item"),
+    })
+}
+
+// cbor.marshal produces standard base64 encoded cbor.
+#[cfg(feature = "cbor")]
+fn cbor_marshal(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Result<Value> {
+    let name = "cbor.marshal";
+    ensure_args_count(span, name, params, args, 1)?;
+
+    let mut bytes = vec![];
+    ciborium::into_writer(&args[0], &mut bytes)
+        .map_err(|e| span.error(&format!("could not serialize to cbor\nCaused by\n{e}")))?;
+    Ok(Value::from(data_encoding::BASE64.encode(&bytes)))
+}
+
+// cbor.unmarshal expects standard base64 encoded cbor.
+#[cfg(feature = "cbor")]
+fn cbor_unmarshal(
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+    _strict: bool,
+) -> Result<Value> {
+    let name = "cbor.unmarshal";
+    ensure_args_count(span, name, params, args, 1)?;
+
+    let encoded_str = ensure_string(name, &params[0], &args[0])?;
+    let bytes = data_encoding::BASE64
+        .decode(encoded_str.as_bytes())
+        .map_err(|e| {
+            params[0]
+                .span()
+                .error(&format!("decode failed\nCaused by\n{e}"))
+        })?;
+    let item: ciborium::Value = ciborium::from_reader(bytes.as_slice()).map_err(|e| {
+        params[0]
+            .span()
+            .error(&format!("could not deserialize cbor\nCaused by\n{e}"))
+    })?;
+    cbor_to_value(item).map_err(|e| {
+        params[0]
+            .span()
+            .error(&format!("could not deserialize cbor\nCaused by\n{e}"))
+    })
+}
+
 #[cfg(feature = "hex")]
 fn hex_decode(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Result<Value> {
     let name = "hex.decode";
@@ -260,6 +429,70 @@ fn urlquery_decode_object(
     Ok(Value::from_map(map))
 }
 
+#[cfg(feature = "hex")]
+fn hex_encode_upper(
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+    _strict: bool,
+) -> Result<Value> {
+    let name = "hex.encode_upper";
+    ensure_args_count(span, name, params, args, 1)?;
+
+    let string = ensure_string(name, &params[0], &args[0])?;
+    Ok(Value::String(
+        data_encoding::HEXUPPER.encode(string.as_bytes()).into(),
+    ))
+}
+
+// Escape a string for use in a url query in the same manner as Go's `url.QueryEscape`.
+// Alphanumerics and `-`, `_`, `.`, `~` are retained, spaces are encoded as `+` and
+// all other bytes are percent-encoded.
+#[cfg(feature = "urlquery")]
+fn query_escape(s: &str) -> String {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+    let mut escaped = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                escaped.push(b as char)
+            }
+            b' ' => escaped.push('+'),
+            _ => {
+                escaped.push('%');
+                escaped.push(HEX[(b >> 4) as usize] as char);
+                escaped.push(HEX[(b & 0xf) as usize] as char);
+            }
+        }
+    }
+    escaped
+}
+
+// Keys are visited in sorted order and each element of a collection value is emitted as a
+// separate `key=value` pair, matching Go's `url.Values.Encode`.
+#[cfg(feature = "urlquery")]
+fn encode_query_object(
+    name: &str,
+    param: &Expr,
+    obj: &alloc::collections::BTreeMap<Value, Value>,
+) -> Result<String> {
+    let mut pairs = vec![];
+    for (key, value) in obj.iter() {
+        let key = query_escape(&ensure_string(name, param, key)?);
+        match value {
+            Value::String(v) => pairs.push(format!("{key}={}", query_escape(v))),
+            _ => {
+                let values = ensure_string_collection(name, param, value)?;
+                for v in values {
+                    pairs.push(format!("{key}={}", query_escape(v)));
+                }
+            }
+        }
+    }
+    Ok(pairs.join("&"))
+}
+
+// Strings are escaped as in OPA. Objects are encoded like `urlquery.encode_object`.
 #[cfg(feature = "urlquery")]
 fn urlquery_encode(
     span: &Span,
@@ -270,19 +503,13 @@ fn urlquery_encode(
     let name = "urlquery.encode";
     ensure_args_count(span, name, params, args, 1)?;
 
-    let s = ensure_string(name, &params[0], &args[0])?;
-    let mut url = match url::Url::parse("https://non-existent") {
-        Ok(v) => v,
-        Err(_) => bail!(params[0].span().error("not a valid url query")),
-    };
-
-    url.query_pairs_mut().append_pair(&s, "");
-    let query_str = url.query().unwrap_or("");
-    if query_str.is_empty() {
-        Ok(Value::String("".into()))
-    } else {
-        Ok(Value::String(query_str[..query_str.len() - 1].into()))
+    if let Value::Object(obj) = &args[0] {
+        return Ok(Value::String(
+            encode_query_object(name, &params[0], obj)?.into(),
+        ));
     }
+    let s = ensure_string(name, &params[0], &args[0])?;
+    Ok(Value::String(query_escape(&s).into()))
 }
 
 #[cfg(feature = "urlquery")]
@@ -296,27 +523,100 @@ fn urlquery_encode_object(
     ensure_args_count(span, name, params, args, 1)?;
 
     let obj = ensure_object(name, &params[0], args[0].clone())?;
-    let mut url = match url::Url::parse("https://non-existent") {
-        Ok(v) => v,
-        Err(_) => bail!(params[0].span().error("not a valid url query")),
-    };
+    Ok(Value::String(
+        encode_query_object(name, &params[0], &obj)?.into(),
+    ))
+}
 
-    for (key, value) in obj.iter() {
-        let key = ensure_string(name, &params[0], key)?;
-        match value {
-            Value::String(v) => {
-                url.query_pairs_mut().append_pair(key.as_ref(), v.as_ref());
-            }
-            _ => {
-                let values = ensure_string_collection(name, &params[0], value)?;
-                for v in values {
-                    url.query_pairs_mut().append_pair(key.as_ref(), v);
+// Qualified name (`prefix:local`) of an element or attribute as written in the document.
+#[cfg(feature = "xml")]
+fn xml_name(node: &roxmltree::Node, namespace: Option<&str>, name: &str) -> Value {
+    match namespace.and_then(|ns| node.lookup_prefix(ns)) {
+        Some(prefix) if !prefix.is_empty() => Value::from(format!("{prefix}:{name}")),
+        _ => Value::from(name),
+    }
+}
+
+// Convert an xml element to a value.
+//
+// - Attributes are placed in an object under the `@attributes` key.
+// - Child elements are placed under their names. Repeated siblings are collapsed into an
+//   array in document order.
+// - Text content (including CDATA) is concatenated, trimmed and placed under `#text`.
+// - An element without attributes and child elements is converted to its text content.
+// - Comments, processing instructions and namespace declarations are ignored.
+#[cfg(feature = "xml")]
+fn xml_element_to_value(node: &roxmltree::Node) -> Value {
+    let mut text = String::new();
+    let mut children = alloc::collections::BTreeMap::new();
+    for child in node.children() {
+        if child.is_element() {
+            let tag = child.tag_name();
+            let key = xml_name(&child, tag.namespace(), tag.name());
+            let value = xml_element_to_value(&child);
+            match children.get_mut(&key) {
+                Some(Value::Array(a)) => Rc::make_mut(a).push(value),
+                Some(existing) => {
+                    let first = core::mem::replace(existing, Value::Null);
+                    *existing = Value::from(vec![first, value]);
+                }
+                None => {
+                    children.insert(key, value);
                 }
             }
+        } else if let Some(t) = child.text().filter(|_| child.is_text()) {
+            text.push_str(t);
         }
     }
 
-    Ok(Value::String(url.query().unwrap_or("").into()))
+    let text = text.trim();
+    let attributes: alloc::collections::BTreeMap<Value, Value> = node
+        .attributes()
+        .map(|a| {
+            (
+                xml_name(node, a.namespace(), a.name()),
+                Value::from(a.value()),
+            )
+        })
+        .collect();
+
+    if attributes.is_empty() && children.is_empty() {
+        return Value::from(text);
+    }
+
+    if !attributes.is_empty() {
+        children.insert(Value::from("@attributes"), Value::from(attributes));
+    }
+    if !text.is_empty() {
+        children.insert(Value::from("#text"), Value::from(text));
+    }
+    Value::from(children)
+}
+
+#[cfg(feature = "xml")]
+fn xml_unmarshal(
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+    _strict: bool,
+) -> Result<Value> {
+    let name = "xml.unmarshal";
+    ensure_args_count(span, name, params, args, 1)?;
+    let xml_str = ensure_string(name, &params[0], &args[0])?;
+    let doc = roxmltree::Document::parse(&xml_str).map_err(|e| {
+        params[0]
+            .span()
+            .error(&format!("could not deserialize xml\nCaused by\n{e}"))
+    })?;
+
+    let root = doc.root_element();
+    let tag = root.tag_name();
+    let mut obj = alloc::collections::BTreeMap::new();
+    obj.insert(
+        xml_name(&root, tag.namespace(), tag.name()),
+        xml_element_to_value(&root),
+    );
+    Ok(Value::from(obj))
 }
 
 #[cfg(feature = "yaml")]
@@ -354,7 +654,102 @@ fn yaml_unmarshal(
     let name = "yaml.unmarshal";
     ensure_args_count(span, name, params, args, 1)?;
     let yaml_str = ensure_string(name, &params[0], &args[0])?;
-    Value::from_yaml_str(&yaml_str).with_context(|| span.error("could not deserialize yaml."))
+    let value = Value::from_yaml_str(&yaml_str)
+        .with_context(|| span.error("could not deserialize yaml."))?;
+    yaml_string_keys(value)
+}
+
+// YAML allows mapping keys of any type, whereas JSON objects require string keys.
+// Non-string keys are converted to their JSON representation, e.g. `1` becomes "1" and
+// `true` becomes "true", so that unmarshalled documents can be marshalled to JSON.
+#[cfg(feature = "yaml")]
+fn yaml_string_keys(value: Value) -> Result<Value> {
+    Ok(match value {
+        Value::Array(items) => Value::from_array(
+            items
+                .iter()
+                .map(|item| yaml_string_keys(item.clone()))
+                .collect::<Result<Vec<Value>>>()?,
+        ),
+        Value::Object(fields) => {
+            let mut map = alloc::collections::BTreeMap::new();
+            for (key, value) in fields.iter() {
+                let key = match key {
+                    Value::String(_) => key.clone(),
+                    _ => Value::String(serde_json::to_string(key)?.into()),
+                };
+                map.insert(key, yaml_string_keys(value.clone())?);
+            }
+            Value::from_map(map)
+        }
+        _ => value,
+    })
+}
+
+#[cfg(feature = "yaml")]
+fn yaml_unmarshal_all(
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+    _strict: bool,
+) -> Result<Value> {
+    let name = "yaml.unmarshal_all";
+    ensure_args_count(span, name, params, args, 1)?;
+    let yaml_str = ensure_string(name, &params[0], &args[0])?;
+
+    // Empty documents, such as those produced by a trailing or repeated `---`, deserialize
+    // to null. They are skipped, as are explicit null documents.
+    let mut documents = vec![];
+    if yaml_str.trim().is_empty() {
+        return Ok(Value::from_array(documents));
+    }
+    for document in serde_yaml::Deserializer::from_str(&yaml_str) {
+        let value = <Value as serde::Deserialize>::deserialize(document)
+            .with_context(|| span.error("could not deserialize yaml."))?;
+        if value != Value::Null {
+            documents.push(yaml_string_keys(value)?);
+        }
+    }
+    Ok(Value::from_array(documents))
+}
+
+#[cfg(feature = "toml")]
+fn toml_is_valid(
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+    _strict: bool,
+) -> Result<Value> {
+    let name = "toml.is_valid";
+    ensure_args_count(span, name, params, args, 1)?;
+
+    let toml_str = ensure_string(name, &params[0], &args[0])?;
+    Ok(Value::Bool(Value::from_toml_str(&toml_str).is_ok()))
+}
+
+// TOML has no null. Keys whose values are null are omitted from the output.
+#[cfg(feature = "toml")]
+fn toml_marshal(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Result<Value> {
+    let name = "toml.marshal";
+    ensure_args_count(span, name, params, args, 1)?;
+    Ok(Value::String(
+        toml::to_string(&args[0])
+            .map_err(|e| span.error(&format!("could not serialize to toml\nCaused by\n{e}")))?
+            .into(),
+    ))
+}
+
+#[cfg(feature = "toml")]
+fn toml_unmarshal(
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+    _strict: bool,
+) -> Result<Value> {
+    let name = "toml.unmarshal";
+    ensure_args_count(span, name, params, args, 1)?;
+    let toml_str = ensure_string(name, &params[0], &args[0])?;
+    Value::from_toml_str(&toml_str).with_context(|| span.error("could not deserialize toml."))
 }
 
 fn json_is_valid(
@@ -402,13 +797,13 @@ fn json_marshal_with_options(
             }
             Value::String(s) if s.as_ref() == "prefix" => bail!(params[1]
                 .span()
-                .error("marshaling option `pretty` must be string")),
+                .error("marshaling option `prefix` must be string")),
             Value::String(s) if s.as_ref() == "indent" && option_value.as_string().is_ok() => {
                 indent = Some(option_value.as_string()?.as_ref().to_string());
             }
             Value::String(s) if s.as_ref() == "indent" => bail!(params[1]
                 .span()
-                .error("marshaling option `pretty` must be string")),
+                .error("marshaling option `indent` must be string")),
             _ => bail!(params[1]
                 .span()
                 .error("marshaling option must be one of `indent`, `prefix` or `pretty`")),
@@ -424,7 +819,8 @@ fn json_marshal_with_options(
     let lines: Vec<String> = serde_json::to_string_pretty(&args[0])
         .map_err(|e| span.error(&format!("could not serialize to json\nCaused by\n{e}")))?
         .split('\n')
-        .map(|line| {
+        .enumerate()
+        .map(|(idx, line)| {
             let mut line = line.to_string();
 
             if let Some(indent) = &indent {
@@ -434,8 +830,10 @@ fn json_marshal_with_options(
                 line = indent.repeat(indentation_level) + start_trimmed;
             }
 
-            if let Some(prefix) = &prefix {
-                line = prefix.to_owned() + &line;
+            // Like Go's json.MarshalIndent, the prefix is not applied to the first line.
+            match &prefix {
+                Some(prefix) if idx > 0 => line = prefix.to_owned() + &line,
+                _ => (),
             }
             line
         })
@@ -444,6 +842,40 @@ fn json_marshal_with_options(
     Ok(Value::from(lines.join("\n")))
 }
 
+// Applies an RFC 7396 merge patch. Null values in the patch delete keys, nested objects
+// are merged recursively and any non-object patch replaces the target.
+fn merge_patch(target: Value, patch: &Value) -> Value {
+    let Value::Object(patch) = patch else {
+        return patch.clone();
+    };
+
+    let mut target = match target {
+        Value::Object(o) => o,
+        _ => Rc::new(alloc::collections::BTreeMap::new()),
+    };
+    let target_ref = Rc::make_mut(&mut target);
+    for (key, value) in patch.iter() {
+        if value == &Value::Null {
+            target_ref.remove(key);
+        } else {
+            let existing = target_ref.remove(key).unwrap_or(Value::Null);
+            target_ref.insert(key.clone(), merge_patch(existing, value));
+        }
+    }
+    Value::Object(target)
+}
+
+fn json_merge_patch(
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+    _strict: bool,
+) -> Result<Value> {
+    let name = "json.merge_patch";
+    ensure_args_count(span, name, params, args, 2)?;
+    Ok(merge_patch(args[0].clone(), &args[1]))
+}
+
 fn json_unmarshal(
     span: &Span,
     params: &[Ref<Expr>],
@@ -455,3 +887,72 @@ fn json_unmarshal(
     let json_str = ensure_string(name, &params[0], &args[0])?;
     Value::from_json_str(&json_str).with_context(|| span.error("could not deserialize json."))
 }
+
+#[cfg(feature = "z85")]
+const Z85_ALPHABET: &[u8; 85] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+// Z85 (https://rfc.zeromq.org/spec/32/) encodes each 4 byte block as 5 characters and, unlike
+// ascii85, does not define padding. Inputs that are not block aligned are therefore rejected.
+#[cfg(feature = "z85")]
+fn z85_encode(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Result<Value> {
+    let name = "z85.encode";
+    ensure_args_count(span, name, params, args, 1)?;
+
+    let string = ensure_string(name, &params[0], &args[0])?;
+    let bytes = string.as_bytes();
+    if bytes.len() % 4 != 0 {
+        bail!(params[0].span().error(&format!(
+            "`{name}` requires input length to be a multiple of 4. Got {} bytes.",
+            bytes.len()
+        )));
+    }
+
+    let mut encoded = String::with_capacity(bytes.len() / 4 * 5);
+    for chunk in bytes.chunks(4) {
+        let mut value = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        let mut block = [0u8; 5];
+        for c in block.iter_mut().rev() {
+            *c = Z85_ALPHABET[(value % 85) as usize];
+            value /= 85;
+        }
+        block.iter().for_each(|c| encoded.push(*c as char));
+    }
+    Ok(Value::String(encoded.into()))
+}
+
+#[cfg(feature = "z85")]
+fn z85_decode(span: &Span, params: &[Ref<Expr>], args: &[Value], strict: bool) -> Result<Value> {
+    let name = "z85.decode";
+    ensure_args_count(span, name, params, args, 1)?;
+
+    let encoded_str = ensure_string(name, &params[0], &args[0])?;
+    let encoded = encoded_str.as_bytes();
+    if encoded.len() % 5 != 0 {
+        bail!(params[0].span().error(&format!(
+            "`{name}` requires input length to be a multiple of 5. Got {} characters.",
+            encoded.len()
+        )));
+    }
+
+    let mut decoded_bytes = Vec::with_capacity(encoded.len() / 5 * 4);
+    for chunk in encoded.chunks(5) {
+        let mut value: u64 = 0;
+        for c in chunk {
+            let Some(digit) = Z85_ALPHABET.iter().position(|a| a == c) else {
+                bail!(params[0].span().error(&format!(
+                    "decode failed\nCaused by\ninvalid z85 character `{}`",
+                    *c as char
+                )));
+            };
+            value = value * 85 + digit as u64;
+        }
+        let value = u32::try_from(value).map_err(|_| {
+            params[0]
+                .span()
+                .error("decode failed\nCaused by\nz85 block exceeds 32 bits")
+        })?;
+        decoded_bytes.extend_from_slice(&value.to_be_bytes());
+    }
+    decoded_bytes_to_value(&params[0], decoded_bytes, strict)
+}
@@ -3,19 +3,26 @@
 
 use crate::ast::{Expr, Ref};
 use crate::builtins;
-use crate::builtins::utils::{ensure_args_count, ensure_string};
+use crate::builtins::utils::{ensure_args_count, ensure_object, ensure_string};
 use crate::*;
 
 use crate::lexer::Span;
 use crate::value::Value;
 
+use hmac::{Hmac, Mac};
 use itertools::Itertools;
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey};
+use sha2::{Sha256, Sha384, Sha512};
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 
 pub fn register(m: &mut builtins::BuiltinsMap<&'static str, builtins::BuiltinFcn>) {
     m.insert("io.jwt.decode", (jwt_decode, 1));
     m.insert("io.jwt.decode_verify", (jwt_decode_verify, 2));
+    m.insert("io.jwt.verify_hs256", (jwt_verify_hs256, 2));
+    m.insert("io.jwt.verify_hs384", (jwt_verify_hs384, 2));
+    m.insert("io.jwt.verify_hs512", (jwt_verify_hs512, 2));
 }
 
 fn decode(span: &Span, jwt: String, strict: bool) -> Result<Value> {
@@ -25,7 +32,7 @@ fn decode(span: &Span, jwt: String, strict: bool) -> Result<Value> {
         .collect_tuple()
     else {
         if strict {
-            bail!(span.error("invalid jwt token"));
+            bail!(span.error("invalid jwt token: expecting header, payload and signature"));
         }
         return Ok(Value::Undefined);
     };
@@ -35,7 +42,7 @@ fn decode(span: &Span, jwt: String, strict: bool) -> Result<Value> {
     let signature = data_encoding::HEXLOWER_PERMISSIVE.encode(&signature);
 
     let signature = Value::String(signature.into());
-    let header = Value::from_json_str(&header)?;
+    let header = Value::from_json_str(&header).with_context(|| span.error("invalid jwt header"))?;
 
     if header["enc"] != Value::Undefined {
         bail!(span.error("JWT is a JWE object, which is not supported"));
@@ -48,7 +55,8 @@ fn decode(span: &Span, jwt: String, strict: bool) -> Result<Value> {
         // Ignore ""
         decode(span, payload[1..payload.len() - 1].to_string(), strict)
     } else {
-        let payload = Value::from_json_str(&payload)?;
+        let payload =
+            Value::from_json_str(&payload).with_context(|| span.error("invalid jwt payload"))?;
         Ok(Value::from_array([header, payload, signature].into()))
     }
 }
@@ -58,16 +66,222 @@ fn jwt_decode(span: &Span, params: &[Ref<Expr>], args: &[Value], strict: bool) -
     ensure_args_count(span, name, params, args, 1)?;
     let jwt = ensure_string(name, &params[0], &args[0])?;
 
-    decode(span, jwt.to_string(), strict) //header, payload, signature, strict)
+    decode(params[0].span(), jwt.to_string(), strict)
+}
+
+// Keys that can verify tokens signed with the given algorithm.
+// The cert may be a PEM encoded certificate or public key, a JWK or a JWK set.
+fn decoding_keys(param: &Expr, cert: &str, algorithm: Algorithm) -> Result<Vec<DecodingKey>> {
+    let invalid_cert =
+        |e: &dyn core::fmt::Display| param.span().error(&format!("invalid cert\nCaused by\n{e}"));
+
+    if cert.trim_start().starts_with('{') {
+        let jwks = match serde_json::from_str::<JwkSet>(cert) {
+            Ok(jwks) => jwks.keys,
+            Err(_) => vec![serde_json::from_str::<Jwk>(cert).map_err(|e| invalid_cert(&e))?],
+        };
+        return jwks
+            .iter()
+            .map(|jwk| DecodingKey::from_jwk(jwk).map_err(|e| invalid_cert(&e)))
+            .collect();
+    }
+
+    let pem = cert.as_bytes();
+    let key = match algorithm {
+        Algorithm::RS256
+        | Algorithm::RS384
+        | Algorithm::RS512
+        | Algorithm::PS256
+        | Algorithm::PS384
+        | Algorithm::PS512 => DecodingKey::from_rsa_pem(pem),
+        Algorithm::ES256 | Algorithm::ES384 => DecodingKey::from_ec_pem(pem),
+        Algorithm::EdDSA => DecodingKey::from_ed_pem(pem),
+        // Tokens signed with a shared secret cannot be verified using a cert.
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => return Ok(vec![]),
+    };
+    match key {
+        Ok(key) => Ok(vec![key]),
+        // A valid key of another type cannot verify the token.
+        Err(_)
+            if DecodingKey::from_rsa_pem(pem).is_ok()
+                || DecodingKey::from_ec_pem(pem).is_ok()
+                || DecodingKey::from_ed_pem(pem).is_ok() =>
+        {
+            Ok(vec![])
+        }
+        Err(e) => Err(invalid_cert(&e)),
+    }
 }
 
 fn jwt_decode_verify(
     span: &Span,
     params: &[Ref<Expr>],
     args: &[Value],
-    _strict: bool,
+    strict: bool,
 ) -> Result<Value> {
     let name = "io.jwt.decode_verify";
     ensure_args_count(span, name, params, args, 2)?;
-    Ok(Value::Undefined)
+
+    let jwt = ensure_string(name, &params[0], &args[0])?;
+    let constraints = ensure_object(name, &params[1], args[1].clone())?;
+
+    let (mut cert, mut secret, mut alg, mut iss, mut aud, mut time) =
+        (None, None, None, None, None, None);
+    for (key, value) in constraints.iter() {
+        let key = key.as_string()?.as_ref();
+        match (key, value) {
+            ("cert", Value::String(s)) => cert = Some(s.clone()),
+            ("secret", Value::String(s)) => secret = Some(s.clone()),
+            ("alg", Value::String(s)) => alg = Some(s.clone()),
+            ("iss", Value::String(s)) => iss = Some(s.clone()),
+            ("aud", Value::String(s)) => aud = Some(s.clone()),
+            ("time", Value::Number(n)) => time = n.as_f64(),
+            ("cert" | "secret" | "alg" | "iss" | "aud", _) => bail!(params[1]
+                .span()
+                .error(&format!("constraint `{key}` must be a string"))),
+            ("time", _) => bail!(params[1].span().error("constraint `time` must be a number")),
+            _ => bail!(params[1]
+                .span()
+                .error(&format!("unknown token validation constraint `{key}`"))),
+        }
+    }
+    if cert.is_none() && secret.is_none() {
+        bail!(params[1]
+            .span()
+            .error("token validation constraints must contain `cert` or `secret`"));
+    }
+
+    let invalid =
+        Value::from_array([Value::Bool(false), Value::new_object(), Value::new_object()].into());
+
+    let decoded = decode(params[0].span(), jwt.to_string(), strict)?;
+    let (header, payload) = (&decoded[0], &decoded[1]);
+    if header == &Value::Undefined {
+        return Ok(Value::Undefined);
+    }
+
+    // Verify the signature using the algorithm specified in the header.
+    let Ok(header_alg) = header["alg"].as_string() else {
+        return Ok(invalid);
+    };
+    if matches!(&alg, Some(alg) if alg != header_alg) {
+        return Ok(invalid);
+    }
+    let Ok(algorithm) = header_alg.parse::<Algorithm>() else {
+        return Ok(invalid);
+    };
+    let keys = match (algorithm, &secret, &cert) {
+        (Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512, Some(secret), _) => {
+            vec![DecodingKey::from_secret(secret.as_bytes())]
+        }
+        (_, _, Some(cert)) => decoding_keys(&params[1], cert, algorithm)?,
+        _ => vec![],
+    };
+    let Some((signing_input, signature)) = jwt.rsplit_once('.') else {
+        return Ok(invalid);
+    };
+    if !keys.iter().any(|key| {
+        matches!(
+            jsonwebtoken::crypto::verify(signature, signing_input.as_bytes(), key, algorithm),
+            Ok(true)
+        )
+    }) {
+        return Ok(invalid);
+    }
+
+    // Check the registered claims.
+    let now = match time {
+        Some(ns) => ns / 1e9,
+        None => jsonwebtoken::get_current_timestamp() as f64,
+    };
+    match &payload["exp"] {
+        Value::Undefined => (),
+        Value::Number(exp) if matches!(exp.as_f64(), Some(exp) if now < exp) => (),
+        _ => return Ok(invalid),
+    }
+    match &payload["nbf"] {
+        Value::Undefined => (),
+        Value::Number(nbf) if matches!(nbf.as_f64(), Some(nbf) if now >= nbf) => (),
+        _ => return Ok(invalid),
+    }
+    if let Some(iss) = &iss {
+        if payload["iss"].as_string().ok() != Some(iss) {
+            return Ok(invalid);
+        }
+    }
+    // A token that specifies an audience is valid only if the expected audience is supplied.
+    let aud_ok = match (&aud, &payload["aud"]) {
+        (None, Value::Undefined) => true,
+        (Some(aud), Value::String(s)) => aud == s,
+        (Some(aud), Value::Array(a)) => a.iter().any(|v| v.as_string().ok() == Some(aud)),
+        _ => false,
+    };
+    if !aud_ok {
+        return Ok(invalid);
+    }
+
+    Ok(Value::from_array(
+        [Value::Bool(true), header.clone(), payload.clone()].into(),
+    ))
+}
+
+// Split the token into the signing input (header.payload) and the decoded signature.
+fn split_signature<'a>(span: &Span, jwt: &'a str) -> Result<(&'a str, Vec<u8>)> {
+    let Some((signing_input, signature)) = jwt.rsplit_once('.') else {
+        bail!(span.error("invalid jwt token: expecting header, payload and signature"));
+    };
+    if signing_input.matches('.').count() != 1 {
+        bail!(span.error("invalid jwt token: expecting header, payload and signature"));
+    }
+    let signature = data_encoding::BASE64URL_NOPAD
+        .decode(signature.as_bytes())
+        .map_err(|e| span.error(&format!("invalid jwt signature\nCaused by\n{e}")))?;
+    Ok((signing_input, signature))
+}
+
+// Mac::verify_slice compares the computed hmac with the signature in constant time.
+fn verify_hs<M: Mac + hmac::digest::KeyInit>(
+    name: &str,
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+) -> Result<Value> {
+    ensure_args_count(span, name, params, args, 2)?;
+
+    let jwt = ensure_string(name, &params[0], &args[0])?;
+    let secret = ensure_string(name, &params[1], &args[1])?;
+    let (signing_input, signature) = split_signature(params[0].span(), &jwt)?;
+
+    let mut hmac = <M as Mac>::new_from_slice(secret.as_bytes())
+        .or_else(|_| bail!(span.error("failed to create hmac instance")))?;
+    hmac.update(signing_input.as_bytes());
+
+    Ok(Value::Bool(hmac.verify_slice(&signature).is_ok()))
+}
+
+fn jwt_verify_hs256(
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+    _strict: bool,
+) -> Result<Value> {
+    verify_hs::<Hmac<Sha256>>("io.jwt.verify_hs256", span, params, args)
+}
+
+fn jwt_verify_hs384(
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+    _strict: bool,
+) -> Result<Value> {
+    verify_hs::<Hmac<Sha384>>("io.jwt.verify_hs384", span, params, args)
+}
+
+fn jwt_verify_hs512(
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+    _strict: bool,
+) -> Result<Value> {
+    verify_hs::<Hmac<Sha512>>("io.jwt.verify_hs512", span, params, args)
 }
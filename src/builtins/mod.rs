@@ -20,10 +20,12 @@ mod graph;
 mod http;
 #[cfg(feature = "jwt")]
 mod jwt;
+#[cfg(feature = "net")]
+pub mod net;
 pub mod numbers;
 mod objects;
 #[cfg(feature = "opa-runtime")]
-mod opa;
+pub mod opa;
 #[cfg(feature = "regex")]
 mod regex;
 #[cfg(feature = "semver")]
@@ -37,7 +39,7 @@ pub mod types;
 mod units;
 mod utils;
 #[cfg(feature = "uuid")]
-mod uuid;
+pub mod uuid;
 
 #[cfg(feature = "opa-testutil")]
 mod test;
@@ -93,7 +95,8 @@ lazy_static! {
 	//graphql::register(&mut m);
 	#[cfg(feature = "http")]
 	http::register(&mut m);
-	//net::register(&mut m);
+	#[cfg(feature = "net")]
+	net::register(&mut m);
 	#[cfg(feature = "uuid")]
 	uuid::register(&mut m);
 	#[cfg(feature = "semver")]
@@ -113,6 +116,7 @@ lazy_static! {
 
 pub fn must_cache(path: &str) -> Option<&'static str> {
     match path {
+        "net.lookup_ip_addr" => Some("net.lookup_ip_addr"),
         "opa.runtime" => Some("opa.runtime"),
         "rand.intn" => Some("rand.intn"),
         "time.now_ns" => Some("time.now_ns"),
@@ -0,0 +1,187 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::ast::{Expr, Ref};
+use crate::builtins;
+use crate::builtins::utils::{ensure_args_count, ensure_string};
+use crate::lexer::Span;
+use crate::value::Value;
+use crate::Resolver;
+use crate::*;
+
+use alloc::collections::BTreeSet;
+use core::net::IpAddr;
+use core::str::FromStr;
+
+use anyhow::{bail, Result};
+use ipnet::{IpNet, Ipv4AddrRange, Ipv6AddrRange};
+
+pub fn register(m: &mut builtins::BuiltinsMap<&'static str, builtins::BuiltinFcn>) {
+    m.insert("net.cidr_contains", (cidr_contains, 2));
+    m.insert("net.cidr_expand", (cidr_expand, 1));
+    m.insert("net.cidr_intersects", (cidr_intersects, 2));
+    m.insert("net.cidr_merge", (cidr_merge, 1));
+    m.insert("net.lookup_ip_addr", (lookup_ip_addr_unconfigured, 1));
+}
+
+// Upper bound on the number of addresses net.cidr_expand produces, to keep a stray
+// large prefix (e.g. an IPv6 /64) from exhausting memory. This is a /16 for IPv4.
+const CIDR_EXPAND_LIMIT: u32 = 16;
+
+fn parse_cidr(arg: &Expr, cidr: &str) -> Result<IpNet> {
+    IpNet::from_str(cidr)
+        .map(|net| net.trunc())
+        .map_err(|_| arg.span().error(&format!("invalid CIDR address `{cidr}`")))
+}
+
+fn parse_ip(ip: &str) -> Option<IpAddr> {
+    // Like Go, IPv4-mapped IPv6 addresses are treated as IPv4 addresses.
+    match IpAddr::from_str(ip).ok()? {
+        IpAddr::V6(v6) => Some(v6.to_ipv4_mapped().map_or(IpAddr::V6(v6), IpAddr::V4)),
+        ip => Some(ip),
+    }
+}
+
+fn cidr_contains(
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+    _strict: bool,
+) -> Result<Value> {
+    let name = "net.cidr_contains";
+    ensure_args_count(span, name, params, args, 2)?;
+
+    let cidr = ensure_string(name, &params[0], &args[0])?;
+    let cidr_or_ip = ensure_string(name, &params[1], &args[1])?;
+
+    let network = parse_cidr(&params[0], &cidr)?;
+
+    // Addresses and networks of different families are never contained.
+    Ok(Value::Bool(match parse_ip(&cidr_or_ip) {
+        Some(ip) => network.contains(&ip),
+        None => network.contains(&parse_cidr(&params[1], &cidr_or_ip)?),
+    }))
+}
+
+fn cidr_expand(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Result<Value> {
+    let name = "net.cidr_expand";
+    ensure_args_count(span, name, params, args, 1)?;
+
+    let cidr = ensure_string(name, &params[0], &args[0])?;
+    let network = parse_cidr(&params[0], &cidr)?;
+
+    let host_bits = u32::from(network.max_prefix_len() - network.prefix_len());
+    if host_bits > CIDR_EXPAND_LIMIT {
+        bail!(params[0].span().error(&format!(
+            "`{name}` cannot expand `{cidr}` since it has more than {} addresses",
+            1u32 << CIDR_EXPAND_LIMIT
+        )));
+    }
+
+    // Unlike IpNet::hosts, the network and broadcast addresses are included.
+    let addresses: BTreeSet<Value> = match network {
+        IpNet::V4(net) => Ipv4AddrRange::new(net.network(), net.broadcast())
+            .map(|ip| Value::String(ip.to_string().into()))
+            .collect(),
+        IpNet::V6(net) => Ipv6AddrRange::new(net.network(), net.broadcast())
+            .map(|ip| Value::String(ip.to_string().into()))
+            .collect(),
+    };
+    Ok(Value::from_set(addresses))
+}
+
+fn cidr_intersects(
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+    _strict: bool,
+) -> Result<Value> {
+    let name = "net.cidr_intersects";
+    ensure_args_count(span, name, params, args, 2)?;
+
+    let cidr1 = ensure_string(name, &params[0], &args[0])?;
+    let cidr2 = ensure_string(name, &params[1], &args[1])?;
+
+    let network1 = parse_cidr(&params[0], &cidr1)?;
+    let network2 = parse_cidr(&params[1], &cidr2)?;
+
+    // Two networks overlap iff one of them contains the other's first address.
+    Ok(Value::Bool(
+        network1.contains(&network2.network()) || network2.contains(&network1.network()),
+    ))
+}
+
+fn cidr_merge(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Result<Value> {
+    let name = "net.cidr_merge";
+    ensure_args_count(span, name, params, args, 1)?;
+
+    let items: Vec<&Value> = match &args[0] {
+        Value::Array(a) => a.iter().collect(),
+        Value::Set(s) => s.iter().collect(),
+        a => bail!(params[0].span().error(&format!(
+            "`{name}` expects array/set of strings. Got `{a}` instead"
+        ))),
+    };
+
+    let mut networks = Vec::with_capacity(items.len());
+    for item in items {
+        let Value::String(s) = item else {
+            bail!(params[0].span().error(&format!(
+                "`{name}` expects array/set of strings. Got `{item}` instead"
+            )));
+        };
+        // Plain addresses are treated as single host networks.
+        let network = match parse_ip(s) {
+            Some(ip) => IpNet::from(ip),
+            None => parse_cidr(&params[0], s)?,
+        };
+        networks.push(network);
+    }
+
+    // Coalesces adjacent and contained networks of each address family.
+    Ok(Value::from_set(
+        IpNet::aggregate(&networks)
+            .into_iter()
+            .map(|network| Value::String(network.to_string().into()))
+            .collect(),
+    ))
+}
+
+// The interpreter calls lookup_ip_addr with the engine's resolver instead.
+fn lookup_ip_addr_unconfigured(
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+    _strict: bool,
+) -> Result<Value> {
+    lookup_ip_addr(None, span, params, args)
+}
+
+pub fn lookup_ip_addr(
+    resolver: Option<&dyn Resolver>,
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+) -> Result<Value> {
+    let name = "net.lookup_ip_addr";
+    ensure_args_count(span, name, params, args, 1)?;
+
+    let host = ensure_string(name, &params[0], &args[0])?;
+    let Some(resolver) = resolver else {
+        bail!(span.error(&format!(
+            "`{name}` requires a resolver. See Engine::set_resolver"
+        )));
+    };
+
+    let addresses = resolver.lookup_ip_addr(&host).map_err(|e| {
+        params[0]
+            .span()
+            .error(&format!("could not resolve `{host}`\nCaused by\n{e}"))
+    })?;
+    Ok(Value::from_set(
+        addresses
+            .into_iter()
+            .map(|ip| Value::String(ip.to_string().into()))
+            .collect(),
+    ))
+}
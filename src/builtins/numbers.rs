@@ -129,8 +129,10 @@ fn range_step(span: &Span, params: &[Ref<Expr>], args: &[Value], strict: bool) -
         _ => (),
     }
 
-    if strict && (!incr.is_integer() || incr <= Number::from(0u64)) {
-        bail!(params[2].span().error("step must be a positive integer"))
+    match incr.is_integer() && incr > Number::from(0u64) {
+        false if strict => bail!(params[2].span().error("step must be a positive integer")),
+        false => return Ok(Value::Undefined),
+        _ => (),
     }
 
     let (incr, num_elements) = match (v2.sub(&v1)?.as_i64(), incr.as_i64()) {
@@ -160,19 +162,30 @@ fn round(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Re
 
 #[cfg(feature = "std")]
 fn intn(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Result<Value> {
+    intn_with_rng(&mut thread_rng(), span, params, args)
+}
+
+/// rand.intn drawing from the given generator.
+///
+/// The interpreter uses this with a seeded generator when the engine has a seed.
+#[cfg(feature = "std")]
+pub fn intn_with_rng<R: Rng>(
+    rng: &mut R,
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+) -> Result<Value> {
     let fcn = "rand.intn";
     ensure_args_count(span, fcn, params, args, 2)?;
     let _ = crate::builtins::utils::ensure_string(fcn, &params[0], &args[0])?;
-    let n = ensure_numeric(fcn, &params[0], &args[1])?;
-
-    Ok(match n.as_u64() {
-        Some(0) => Value::from(0u64),
-        Some(n) => {
-            // TODO: bounds checking; arbitrary precision
-            let mut rng = thread_rng();
-            let v = rng.gen_range(0..n);
-            Value::from(v)
-        }
-        _ => Value::Undefined,
-    })
+    let n = ensure_numeric(fcn, &params[1], &args[1])?;
+
+    // Like OPA, the sign of n is ignored.
+    Ok(
+        match n.as_i64().map(i64::unsigned_abs).or_else(|| n.as_u64()) {
+            Some(0) => Value::from(0u64),
+            Some(n) => Value::from(rng.gen_range(0..n)),
+            _ => Value::Undefined,
+        },
+    )
 }
@@ -3,6 +3,8 @@
 
 use crate::ast::{Expr, Ref};
 use crate::builtins;
+#[cfg(feature = "jsonschema")]
+use crate::builtins::utils::ensure_string;
 use crate::builtins::utils::{ensure_args_count, ensure_array, ensure_object};
 use crate::lexer::Span;
 use crate::Rc;
@@ -24,23 +26,31 @@ pub fn register(m: &mut builtins::BuiltinsMap<&'static str, builtins::BuiltinFcn
     m.insert("object.subset", (subset, 2));
     m.insert("object.union", (object_union, 2));
     m.insert("object.union_n", (object_union_n, 1));
+    m.insert("object.values", (values, 1));
 
     #[cfg(feature = "jsonschema")]
     {
+        m.insert("json.is_valid_with_schema", (json_is_valid_with_schema, 2));
         m.insert("json.match_schema", (json_match_schema, 2));
+        m.insert(
+            "json.match_schema_structured",
+            (json_match_schema_structured, 2),
+        );
         m.insert("json.verify_schema", (json_verify_schema, 1));
     }
 }
 
 fn json_filter_impl(v: &Value, filter: &Value) -> Value {
     let filters = match filter {
-        Value::Object(fields) if fields.len() == 1 && filter[&Value::Null] == Value::Null => {
-            return v.clone()
-        }
         Value::Object(fields) if !fields.is_empty() => fields,
         _ => return v.clone(),
     };
 
+    // A path ending here keeps the entire subtree, even if longer paths share its prefix.
+    if filter[&Value::Null] == Value::Null {
+        return v.clone();
+    }
+
     match v {
         Value::Array(_) => {
             let mut items = vec![];
@@ -288,6 +298,14 @@ fn keys(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Res
     Ok(Value::from_set(obj.keys().cloned().collect()))
 }
 
+// Not an OPA builtin. Values are returned in key order.
+fn values(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Result<Value> {
+    let name = "object.values";
+    ensure_args_count(span, name, params, args, 1)?;
+    let obj = ensure_object(name, &params[0], args[0].clone())?;
+    Ok(Value::from_array(obj.values().cloned().collect()))
+}
+
 fn remove(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Result<Value> {
     let name = "object.remove";
     ensure_args_count(span, name, params, args, 2)?;
@@ -390,16 +408,70 @@ fn object_union_n(
     Ok(u)
 }
 
+// Maximum number of compiled schemas kept around. Schemas are typically validated against
+// many documents, and compiling a large schema dominates the cost of validating one.
+#[cfg(all(feature = "jsonschema", feature = "std"))]
+const JSON_SCHEMA_CACHE_SIZE: usize = 32;
+
+// Least recently used cache of compiled schemas keyed by schema source.
+#[cfg(all(feature = "jsonschema", feature = "std"))]
+#[derive(Default)]
+struct JsonSchemaCache {
+    entries: BTreeMap<String, (Rc<jsonschema::Validator>, u64)>,
+    tick: u64,
+}
+
+#[cfg(all(feature = "jsonschema", feature = "std"))]
+impl JsonSchemaCache {
+    fn get(&mut self, schema_str: &str) -> Option<Rc<jsonschema::Validator>> {
+        self.tick += 1;
+        let (validator, last_used) = self.entries.get_mut(schema_str)?;
+        *last_used = self.tick;
+        Some(validator.clone())
+    }
+
+    fn insert(&mut self, schema_str: String, validator: Rc<jsonschema::Validator>) {
+        if self.entries.len() >= JSON_SCHEMA_CACHE_SIZE {
+            if let Some(lru) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(s, _)| s.clone())
+            {
+                self.entries.remove(&lru);
+            }
+        }
+        self.entries.insert(schema_str, (validator, self.tick));
+    }
+}
+
 #[cfg(feature = "jsonschema")]
-fn compile_json_schema(param: &Ref<Expr>, arg: &Value) -> Result<jsonschema::Validator> {
+fn compile_json_schema(param: &Ref<Expr>, arg: &Value) -> Result<Rc<jsonschema::Validator>> {
     let schema_str = match arg {
         Value::String(schema_str) => schema_str.as_ref().to_string(),
         _ => arg.to_json_str()?,
     };
 
+    #[cfg(feature = "std")]
+    std::thread_local! {
+        static CACHE: core::cell::RefCell<JsonSchemaCache> = core::cell::RefCell::default();
+    }
+
+    #[cfg(feature = "std")]
+    if let Some(validator) = CACHE.with(|cache| cache.borrow_mut().get(&schema_str)) {
+        return Ok(validator);
+    }
+
+    // The draft (4, 6, 7, 2019-09 or 2020-12) is detected from the `$schema` keyword,
+    // defaulting to draft 7.
     if let Ok(schema) = serde_json::from_str(&schema_str) {
         match jsonschema::validator_for(&schema) {
-            Ok(schema) => return Ok(schema),
+            Ok(validator) => {
+                let validator = Rc::new(validator);
+                #[cfg(feature = "std")]
+                CACHE.with(|cache| cache.borrow_mut().insert(schema_str, validator.clone()));
+                return Ok(validator);
+            }
             Err(e) => bail!(e.to_string()),
         }
     }
@@ -441,20 +513,94 @@ fn json_match_schema(
     // The following is expected to succeed.
     let document: serde_json::Value = serde_json::from_str(&args[0].to_json_str()?)?;
 
+    validate_json_document(&params[1], &args[1], &document, strict, false)
+}
+
+#[cfg(feature = "jsonschema")]
+fn json_match_schema_structured(
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+    strict: bool,
+) -> Result<Value> {
+    let name = "json.match_schema_structured";
+    ensure_args_count(span, name, params, args, 2)?;
+
+    // The following is expected to succeed.
+    let document: serde_json::Value = serde_json::from_str(&args[0].to_json_str()?)?;
+
+    validate_json_document(&params[1], &args[1], &document, strict, true)
+}
+
+#[cfg(feature = "jsonschema")]
+fn schema_error_to_value(e: jsonschema::ValidationError, structured: bool) -> Value {
+    if !structured {
+        return Value::String(e.to_string().into());
+    }
+
+    Value::from_map(BTreeMap::from([
+        (
+            Value::from("instanceLocation"),
+            Value::from(e.instance_path.as_str()),
+        ),
+        (
+            Value::from("keywordLocation"),
+            Value::from(e.schema_path.as_str()),
+        ),
+        (Value::from("message"), Value::from(e.to_string())),
+    ]))
+}
+
+#[cfg(feature = "jsonschema")]
+fn validate_json_document(
+    param: &Ref<Expr>,
+    arg: &Value,
+    document: &serde_json::Value,
+    strict: bool,
+    structured: bool,
+) -> Result<Value> {
     Ok(Value::from_array(
-        match compile_json_schema(&params[1], &args[1]) {
-            Ok(schema) => match schema.validate(&document) {
+        match compile_json_schema(param, arg) {
+            Ok(schema) => match schema.validate(document) {
                 Ok(_) => [Value::Bool(true), Value::Null],
                 Err(e) => [
                     Value::Bool(false),
-                    Value::from_array(e.map(|e| Value::String(e.to_string().into())).collect()),
+                    Value::from_array(e.map(|e| schema_error_to_value(e, structured)).collect()),
                 ],
             },
-            Err(e) if strict => bail!(params[1]
-                .span()
-                .error(format!("invalid schema: {e}").as_str())),
+            Err(e) if strict => bail!(param.span().error(format!("invalid schema: {e}").as_str())),
             Err(e) => [Value::Bool(false), Value::String(e.to_string().into())],
         }
         .to_vec(),
     ))
 }
+
+#[cfg(feature = "jsonschema")]
+fn json_is_valid_with_schema(
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+    strict: bool,
+) -> Result<Value> {
+    let name = "json.is_valid_with_schema";
+    ensure_args_count(span, name, params, args, 2)?;
+
+    let json_str = ensure_string(name, &params[0], &args[0])?;
+
+    // Input that is not JSON at all is reported as a single error string,
+    // whereas schema violations are reported as an array of errors.
+    let document: serde_json::Value = match serde_json::from_str(&json_str) {
+        Ok(document) => document,
+        Err(e) => {
+            return Ok(Value::from_array(
+                [
+                    Value::Bool(false),
+                    Value::String(format!("invalid json: {e}").into()),
+                ]
+                .to_vec(),
+            ))
+        }
+    };
+
+    validate_json_document(&params[1], &args[1], &document, strict, false)
+}
@@ -18,8 +18,26 @@ pub fn register(m: &mut builtins::BuiltinsMap<&'static str, builtins::BuiltinFcn
 }
 
 fn opa_runtime(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Result<Value> {
+    runtime(None, span, params, args)
+}
+
+/// opa.runtime returning the runtime object supplied by the engine, if any.
+///
+/// Without one, information about regorus is returned along with empty `env` and
+/// `config` objects. The process environment is never read since it could leak
+/// confidential data.
+pub fn runtime(
+    runtime: Option<&Value>,
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+) -> Result<Value> {
     let name = "opa.runtime";
     ensure_args_count(span, name, params, args, 0)?;
+    if let Some(runtime) = runtime {
+        return Ok(runtime.clone());
+    }
+
     let mut obj = BTreeMap::new();
 
     obj.insert(
@@ -37,18 +55,8 @@ fn opa_runtime(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool)
         Value::String("0.60.0".into()),
     );
 
-    // Emitting environment variables could lead to confidential data being leaked.
-    #[cfg(feature = "std")]
-    if false {
-        obj.insert(
-            Value::String("env".into()),
-            Value::from_map(
-                std::env::vars()
-                    .map(|(k, v)| (Value::String(k.into()), Value::String(v.into())))
-                    .collect(),
-            ),
-        );
-    }
+    obj.insert(Value::String("env".into()), Value::new_object());
+    obj.insert(Value::String("config".into()), Value::new_object());
 
     let features = [
         #[cfg(feature = "base64")]
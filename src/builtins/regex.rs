@@ -25,6 +25,64 @@ pub fn register(m: &mut builtins::BuiltinsMap<&'static str, builtins::BuiltinFcn
     m.insert("regex.template_match", (regex_template_match, 4));
 }
 
+// Maximum number of compiled patterns kept around. Policies tend to use a small set of
+// patterns repeatedly, so a bounded cache avoids recompiling them on every call while
+// dynamically constructed patterns cannot grow it without limit.
+#[cfg(feature = "std")]
+const REGEX_CACHE_SIZE: usize = 100;
+
+// Least recently used cache of compiled patterns.
+#[cfg(feature = "std")]
+#[derive(Default)]
+struct RegexCache {
+    entries: Map<String, (Regex, u64)>,
+    tick: u64,
+}
+
+#[cfg(feature = "std")]
+impl RegexCache {
+    fn get(&mut self, pattern: &str) -> Option<Regex> {
+        self.tick += 1;
+        let (regex, last_used) = self.entries.get_mut(pattern)?;
+        *last_used = self.tick;
+        Some(regex.clone())
+    }
+
+    fn insert(&mut self, pattern: &str, regex: Regex) {
+        if self.entries.len() >= REGEX_CACHE_SIZE {
+            if let Some(lru) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(p, _)| p.clone())
+            {
+                self.entries.remove(&lru);
+            }
+        }
+        self.entries.insert(pattern.into(), (regex, self.tick));
+    }
+}
+
+fn compile(arg: &Expr, pattern: &str) -> Result<Regex> {
+    #[cfg(feature = "std")]
+    {
+        std::thread_local! {
+            static CACHE: core::cell::RefCell<RegexCache> = core::cell::RefCell::default();
+        }
+
+        if let Some(regex) = CACHE.with(|cache| cache.borrow_mut().get(pattern)) {
+            return Ok(regex);
+        }
+
+        let regex = Regex::new(pattern).or_else(|_| bail!(arg.span().error("invalid regex")))?;
+        CACHE.with(|cache| cache.borrow_mut().insert(pattern, regex.clone()));
+        Ok(regex)
+    }
+
+    #[cfg(not(feature = "std"))]
+    Regex::new(pattern).or_else(|_| bail!(arg.span().error("invalid regex")))
+}
+
 fn find_all_string_submatch_n(
     span: &Span,
     params: &[Ref<Expr>],
@@ -38,8 +96,7 @@ fn find_all_string_submatch_n(
     let value = ensure_string(name, &params[1], &args[1])?;
     let n = ensure_numeric(name, &params[2], &args[2])?;
 
-    let pattern =
-        Regex::new(&pattern).or_else(|_| bail!(params[0].span().error("invalid regex")))?;
+    let pattern = compile(&params[0], &pattern)?;
 
     if !n.is_integer() {
         bail!(params[2].span().error("n must be an integer"));
@@ -80,8 +137,7 @@ fn find_n(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> R
     let value = ensure_string(name, &params[1], &args[1])?;
     let n = ensure_numeric(name, &params[2], &args[2])?;
 
-    let pattern =
-        Regex::new(&pattern).or_else(|_| bail!(params[0].span().error("invalid regex")))?;
+    let pattern = compile(&params[0], &pattern)?;
 
     if !n.is_integer() {
         bail!(params[2].span().error("n must be an integer"));
@@ -120,8 +176,7 @@ pub fn regex_match(
     let pattern = ensure_string(name, &params[0], &args[0])?;
     let value = ensure_string(name, &params[1], &args[1])?;
 
-    let pattern =
-        Regex::new(&pattern).or_else(|_| bail!(params[0].span().error("invalid regex")))?;
+    let pattern = compile(&params[0], &pattern)?;
     Ok(Value::Bool(pattern.is_match(&value)))
 }
 
@@ -138,11 +193,9 @@ fn regex_replace(
     let pattern = ensure_string(name, &params[1], &args[1])?;
     let value = ensure_string(name, &params[2], &args[2])?;
 
-    let pattern = match Regex::new(&pattern) {
-        Ok(p) => p,
-        // TODO: This behavior is due to OPA test not raising error. Should we raise error?
-        _ => return Ok(Value::Undefined),
-    };
+    // Invalid patterns are errors. In non-strict mode they evaluate to undefined,
+    // which is what OPA's conformance tests expect.
+    let pattern = compile(&params[1], &pattern)?;
 
     Ok(Value::String(
         pattern.replace_all(&s, value.as_ref()).into(),
@@ -155,14 +208,28 @@ fn regex_split(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool)
     let pattern = ensure_string(name, &params[0], &args[0])?;
     let value = ensure_string(name, &params[1], &args[1])?;
 
-    let pattern =
-        Regex::new(&pattern).or_else(|_| bail!(params[0].span().error("invalid regex")))?;
-    Ok(Value::from_array(
-        pattern
-            .split(&value)
-            .map(|s| Value::String(s.into()))
-            .collect::<Vec<Value>>(),
-    ))
+    let regex = compile(&params[0], &pattern)?;
+
+    // Mirror Go's Regexp.Split which, unlike Regex::split, does not produce empty
+    // strings for empty matches at the start and end of the input.
+    if !pattern.is_empty() && value.is_empty() {
+        return Ok(Value::from_array(vec![Value::String("".into())]));
+    }
+
+    let mut parts = vec![];
+    let (mut beg, mut end) = (0, 0);
+    for m in regex.find_iter(&value) {
+        end = m.start();
+        if m.end() != 0 {
+            parts.push(Value::String(value[beg..end].into()));
+        }
+        beg = m.end();
+    }
+    if end != value.len() {
+        parts.push(Value::String(value[beg..].into()));
+    }
+
+    Ok(Value::from_array(parts))
 }
 
 fn regex_template_match(
@@ -178,40 +245,57 @@ fn regex_template_match(
     let delimiter_start = ensure_string(name, &params[2], &args[2])?;
     let delimiter_end = ensure_string(name, &params[3], &args[3])?;
 
-    let delimiter_start = delimiter_start.as_ref();
-    let delimiter_end = delimiter_end.as_ref();
-    let mut template = template.as_ref();
-    let mut value = value.as_ref();
-
-    while let (Some(start), Some(end)) =
-        (template.find(delimiter_start), template.find(delimiter_end))
-    {
-        if start >= end {
-            return Ok(Value::Undefined);
-        }
-        // Match precesing literal (if any)
-        if template[0..start] != value[0..start] {
-            return Ok(Value::Bool(false));
+    ensure_delimiter(name, &params[2], &delimiter_start)?;
+    ensure_delimiter(name, &params[3], &delimiter_end)?;
+
+    // Translate the template into a single anchored regex where literal text is
+    // escaped and each (possibly nested) delimited section is a group.
+    // This follows OPA's topdown/regex_template.go, which is derived from gorilla/mux.
+    // Unlike OPA, delimiters may be longer than one character.
+    let mut pattern = String::from("^");
+    let mut level = 0;
+    let (mut literal_start, mut section_start) = (0, 0);
+    let mut idx = 0;
+    while idx < template.len() {
+        let rest = &template[idx..];
+        if rest.starts_with(delimiter_start.as_ref()) {
+            level += 1;
+            if level == 1 {
+                pattern.push_str(&regex::escape(&template[literal_start..idx]));
+                section_start = idx + delimiter_start.len();
+            }
+            idx += delimiter_start.len();
+        } else if rest.starts_with(delimiter_end.as_ref()) {
+            level -= 1;
+            if level == 0 {
+                pattern.push('(');
+                pattern.push_str(&template[section_start..idx]);
+                pattern.push(')');
+                literal_start = idx + delimiter_end.len();
+            } else if level < 0 {
+                break;
+            }
+            idx += delimiter_end.len();
+        } else {
+            idx += rest.chars().next().map_or(1, char::len_utf8);
         }
+    }
+    if level != 0 {
+        bail!(params[0]
+            .span()
+            .error(&format!("unbalanced delimiters in template `{template}`")));
+    }
+    pattern.push_str(&regex::escape(&template[literal_start..]));
+    pattern.push('$');
 
-        // Fetch pattern, excluding delimiters.
-        let pattern = Regex::new(&template[start + delimiter_start.len()..end])
-            .or_else(|_| bail!(params[0].span().error("invalid regex")))?;
-
-        // Skip preceding literal in value.
-        value = &value[start..];
-
-        let m = match pattern.find(value) {
-            Some(m) if m.start() == 0 => m,
-            _ => return Ok(Value::Bool(false)),
-        };
-        // Skip match in string.
-        value = &value[m.len()..];
+    Ok(Value::Bool(compile(&params[0], &pattern)?.is_match(&value)))
+}
 
-        // Skip regex and delimiter in template.
-        template = &template[end + delimiter_end.len()..];
+fn ensure_delimiter(name: &str, arg: &Expr, delimiter: &str) -> Result<()> {
+    if delimiter.is_empty() {
+        bail!(arg
+            .span()
+            .error(&format!("`{name}` expects delimiter to be non-empty")));
     }
-
-    // Ensure that ending literal matches.
-    Ok(Value::Bool(template == value))
+    Ok(())
 }
@@ -12,6 +12,9 @@ use crate::number::Number;
 use crate::value::Value;
 use crate::*;
 
+use core::iter::Peekable;
+use core::str::Chars;
+
 use anyhow::{bail, Result};
 
 pub fn register(m: &mut builtins::BuiltinsMap<&'static str, builtins::BuiltinFcn>) {
@@ -45,6 +48,7 @@ fn concat(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> R
     let name = "concat";
     ensure_args_count(span, name, params, args, 2)?;
     let delimiter = ensure_string(name, &params[0], &args[0])?;
+    // Sets are ordered, so set members are joined in sorted order.
     let collection = ensure_string_collection(name, &params[1], &args[1])?;
     Ok(Value::String(collection.join(&delimiter).into()))
 }
@@ -68,13 +72,16 @@ fn endswith(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) ->
 fn format_int(span: &Span, params: &[Ref<Expr>], args: &[Value], strict: bool) -> Result<Value> {
     let name = "format_int";
     ensure_args_count(span, name, params, args, 2)?;
-    let mut n = ensure_numeric(name, &params[0], &args[0])?;
-    let mut sign = "";
-    if n < Number::from(0u64) {
-        n = n.abs();
-        sign = "-";
-    }
-    let n = n.floor();
+    let n = ensure_numeric(name, &params[0], &args[0])?;
+
+    // Truncate toward zero. Values in (-1, 0) become 0 and are printed without a sign.
+    let negative = n < Number::from(0u64);
+    let n = n.abs().floor();
+    let sign = if negative && n != Number::from(0u64) {
+        "-"
+    } else {
+        ""
+    };
 
     let base = ensure_numeric(name, &params[1], &args[1])?;
 
@@ -141,7 +148,7 @@ fn replace(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) ->
 }
 
 fn split(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Result<Value> {
-    let name = "replace";
+    let name = "split";
     ensure_args_count(span, name, params, args, 2)?;
     let s = ensure_string(name, &params[0], &args[0])?;
     let delimiter = ensure_string(name, &params[1], &args[1])?;
@@ -197,21 +204,194 @@ fn to_string(v: &Value, unescape: bool) -> String {
     }
 }
 
-enum Width {
-    None,
-    LeadingZeros(usize),
-    Cell(usize),
-    Decimals(usize),
+// Flags, width and precision of a `%[flags][width][.precision]verb` directive.
+// See https://pkg.go.dev/fmt
+#[derive(Default)]
+struct FormatSpec {
+    plus: bool,
+    minus: bool,
+    sharp: bool,
+    space: bool,
+    zero: bool,
+    width: Option<usize>,
+    precision: Option<usize>,
 }
 
-fn apply_width(w: Width, s: String) -> String {
-    match w {
-        Width::LeadingZeros(n) if n > s.len() => "0".repeat(n - s.len()) + &s,
-        Width::Cell(n) if n > s.len() => " ".repeat(n - s.len()) + &s,
-        _ => s,
+impl FormatSpec {
+    fn sign(&self, negative: bool) -> &'static str {
+        match (negative, self.plus, self.space) {
+            (true, _, _) => "-",
+            (_, true, _) => "+",
+            (_, _, true) => " ",
+            _ => "",
+        }
+    }
+
+    // Pads with spaces to the width, on the right if the minus flag is set.
+    fn pad(&self, s: String) -> String {
+        let len = s.chars().count();
+        match self.width {
+            Some(w) if w > len => {
+                let padding = " ".repeat(w - len);
+                if self.minus {
+                    s + &padding
+                } else {
+                    padding + &s
+                }
+            }
+            _ => s,
+        }
+    }
+
+    // Follows fmtInteger in Go's fmt/format.go. The digits are already in the verb's base.
+    fn format_integer(&self, negative: bool, mut digits: String, verb: char) -> String {
+        // Zero padding is done via the precision, leaving room for the sign but not the prefix.
+        let precision = match (self.precision, self.width) {
+            (Some(0), _) if digits == "0" => return self.pad(String::new()),
+            (Some(p), _) => p,
+            (None, Some(w)) if self.zero && !self.minus => {
+                w.saturating_sub(self.sign(negative).len())
+            }
+            _ => 0,
+        };
+        if digits.len() < precision {
+            digits = "0".repeat(precision - digits.len()) + &digits;
+        }
+
+        let prefix = match verb {
+            'b' if self.sharp => "0b",
+            'o' if self.sharp && !digits.starts_with('0') => "0",
+            'O' => "0o",
+            'x' if self.sharp => "0x",
+            'X' if self.sharp => "0X",
+            _ => "",
+        };
+        self.pad(format!("{}{prefix}{digits}", self.sign(negative)))
+    }
+
+    // Follows fmtFloat in Go's fmt/format.go. %v is formatted as %g.
+    fn format_float(&self, v: f64, verb: char) -> String {
+        let body = match verb {
+            'e' | 'E' => go_exponent(format!("{:.*e}", self.precision.unwrap_or(6), v.abs())),
+            'f' | 'F' => format!("{:.*}", self.precision.unwrap_or(6), v.abs()),
+            _ => format_general(v.abs(), self.precision),
+        };
+        let body = match verb {
+            'E' | 'G' => body.to_uppercase(),
+            _ => body,
+        };
+
+        let sign = self.sign(v < 0.0);
+        match self.width {
+            Some(w) if self.zero && !self.minus && w > sign.len() + body.len() => {
+                format!("{sign}{}{body}", "0".repeat(w - sign.len() - body.len()))
+            }
+            _ => self.pad(format!("{sign}{body}")),
+        }
+    }
+
+    fn format_string(&self, s: &str) -> String {
+        match self.precision {
+            Some(p) => self.pad(s.chars().take(p).collect()),
+            None => self.pad(s.to_string()),
+        }
+    }
+}
+
+// Returns the sign and the digits of an integer in the verb's base.
+fn integer_digits(n: &Number, verb: char) -> Option<(bool, String)> {
+    let (negative, u) = match (n.as_i128(), n.as_u128()) {
+        (Some(i), _) => (i < 0, i.unsigned_abs()),
+        (_, Some(u)) => (false, u),
+        // Larger integers can still be printed in decimal.
+        _ if verb == 'd' => {
+            let s = n.format_decimal();
+            return Some((s.starts_with('-'), s.trim_start_matches('-').to_string()));
+        }
+        _ => return None,
+    };
+    Some((
+        negative,
+        match verb {
+            'b' => format!("{u:b}"),
+            'o' | 'O' => format!("{u:o}"),
+            'x' => format!("{u:x}"),
+            'X' => format!("{u:X}"),
+            _ => u.to_string(),
+        },
+    ))
+}
+
+// Rust writes exponents as `e3` and `e-7` whereas Go writes `e+03` and `e-07`.
+fn go_exponent(s: String) -> String {
+    match s.split_once('e') {
+        Some((mantissa, exp)) => {
+            let (sign, digits) = match exp.strip_prefix('-') {
+                Some(digits) => ('-', digits),
+                None => ('+', exp),
+            };
+            format!("{mantissa}e{sign}{digits:0>2}")
+        }
+        None => s,
     }
 }
 
+// Follows %g in Go's strconv.FormatFloat. Without a precision the shortest representation is
+// used. %e is used for exponents below -4 or at least the precision (6 if not specified).
+fn format_general(v: f64, precision: Option<usize>) -> String {
+    let scientific = match precision {
+        Some(p) => format!("{:.*e}", p.max(1) - 1, v),
+        None => format!("{v:e}"),
+    };
+    let (mantissa, exp) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exp: i64 = exp.parse().unwrap_or_default();
+    let digits = mantissa.replace('.', "");
+    let digits = match digits.trim_end_matches('0') {
+        "" => "0",
+        d => d,
+    };
+
+    let num_digits = digits.len() as i64;
+    let eprec = match precision {
+        Some(p) if p.max(1) as i64 > num_digits && num_digits > exp => num_digits,
+        Some(p) => p.max(1) as i64,
+        None => 6,
+    };
+
+    if exp < -4 || exp >= eprec {
+        let (first, rest) = digits.split_at(1);
+        match rest {
+            "" => go_exponent(format!("{first}e{exp}")),
+            _ => go_exponent(format!("{first}.{rest}e{exp}")),
+        }
+    } else if exp < 0 {
+        format!("0.{}{digits}", "0".repeat((-exp - 1) as usize))
+    } else {
+        let point = exp as usize + 1;
+        if digits.len() <= point {
+            format!("{digits}{}", "0".repeat(point - digits.len()))
+        } else {
+            format!("{}.{}", &digits[..point], &digits[point..])
+        }
+    }
+}
+
+// Like Go, widths and precisions above this are rejected rather than padded.
+const MAX_FORMAT_NUMBER: usize = 1_000_000;
+
+fn parse_format_number(chars: &mut Peekable<Chars>) -> Option<usize> {
+    let mut n = None;
+    while let Some(d) = chars.peek().and_then(|c| c.to_digit(10)) {
+        n = Some(
+            n.unwrap_or(0usize)
+                .saturating_mul(10)
+                .saturating_add(d as usize),
+        );
+        chars.next();
+    }
+    n
+}
+
 fn sprintf(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Result<Value> {
     let name = "sprintf";
     ensure_args_count(span, name, params, args, 2)?;
@@ -221,55 +401,53 @@ fn sprintf(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) ->
     let mut s = String::default();
     let mut args_idx = 0usize;
     let mut chars = fmt.chars().peekable();
+    let fmt_span = params[0].span();
     let args_span = params[1].span();
-    loop {
-        let (verb, width) = match chars.next() {
-            Some('%') => match chars.next() {
-                Some('%') => {
-                    s.push('%');
-                    continue;
-                }
-                Some(c) if c == '.' || c.is_numeric() => {
-                    let first_char = c;
-                    let mut w = 0;
-                    if c != '.' {
-                        w = c.to_digit(10).expect("could not get digit from char");
-                    }
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            s.push(c);
+            continue;
+        }
 
-                    while chars.peek().map(|c| c.is_numeric()) == Some(true) {
-                        w = w * 10
-                            + chars
-                                .next()
-                                .expect("could not get next digit")
-                                .to_digit(10)
-                                .expect("could not get digit from char");
-                    }
-                    let width = match first_char {
-                        '0' => Width::LeadingZeros(w as usize),
-                        '.' => Width::Decimals(w as usize),
-                        _ => Width::Cell(w as usize),
-                    };
-                    match chars.next() {
-                        Some(c) => (c, width),
-                        _ => {
-                            let span = params[0].span();
-                            bail!(span.error(
-                                "missing format verb after `%width` at end of format string"
-                            ));
-                        }
-                    }
-                }
-                Some(c) => (c, Width::None),
-                None => {
-                    let span = params[0].span();
-                    bail!(span.error("missing format verb after `%` at end of format string"));
-                }
-            },
-            Some(c) => {
-                s.push(c);
+        let mut spec = FormatSpec::default();
+        while let Some(&c) = chars.peek() {
+            match c {
+                '+' => spec.plus = true,
+                '-' => spec.minus = true,
+                '#' => spec.sharp = true,
+                ' ' => spec.space = true,
+                '0' => spec.zero = true,
+                _ => break,
+            }
+            chars.next();
+        }
+
+        spec.width = parse_format_number(&mut chars);
+        if spec.width.is_some_and(|w| w > MAX_FORMAT_NUMBER) {
+            s.push_str("%!(BADWIDTH)");
+            spec.width = None;
+        }
+        if chars.peek() == Some(&'.') {
+            chars.next();
+            spec.precision = Some(parse_format_number(&mut chars).unwrap_or(0));
+            if spec.precision.is_some_and(|p| p > MAX_FORMAT_NUMBER) {
+                s.push_str("%!(BADPREC)");
+                spec.precision = None;
+            }
+        }
+
+        let verb = match chars.next() {
+            Some('%') => {
+                s.push('%');
                 continue;
             }
-            None => break,
+            Some(c) if "bcdeEfFgGoOsvxX".contains(c) => c,
+            Some(c) => {
+                bail!(fmt_span.error(&format!("unsupported format verb %{c}")));
+            }
+            None => {
+                bail!(fmt_span.error("missing format verb after `%` at end of format string"));
+            }
         };
 
         if args_idx >= args.len() {
@@ -279,131 +457,86 @@ fn sprintf(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) ->
         let arg = &args[args_idx];
         args_idx += 1;
 
-        // Handle Golang flags.
-        let mut emit_sign = false;
-        let mut leave_space_for_elided_sign = false;
-        match chars.peek() {
-            Some('+') => {
-                emit_sign = true;
-                chars.next();
-            }
-            Some(' ') => {
-                leave_space_for_elided_sign = true;
-                chars.next();
-            }
-            _ => (),
-        }
-
-        let get_sign_value = |f: &Number| match (emit_sign, f) {
-            (_, v) if v < &Number::from(0.0) => ("-", v.clone()),
-            (true, v) => ("+", v.clone()),
-            (false, v) if leave_space_for_elided_sign => (" ", v.clone()),
-            (false, v) => ("", v.clone()),
-        };
-
-        // Handle Golang printing verbs.
-        // https://pkg.go.dev/fmt
-        match (verb, arg) {
-            ('s', Value::String(sv)) => s += sv.as_ref(),
-            ('s', v) => s += &to_string(v, false),
-
-            ('v', _) => s += &to_string(arg, false),
-            ('b', Value::Number(f)) if f.is_integer() => {
-                let (sign, v) = get_sign_value(f);
-                s += sign;
-                s += v.format_bin().as_str()
-            }
-            ('c', Value::Number(f)) if f.is_integer() => {
-                // TODO: range error
-                let ch_opt = f.as_u64().map(|ival| char::from_u32(ival as u32));
-                match ch_opt {
-                    Some(Some(c)) => s.push(c),
+        // Like OPA, integers are formatted as big integers, other numbers as floats
+        // and all other values as strings.
+        match arg {
+            Value::Number(n) => match verb {
+                'b' | 'c' | 'd' | 'o' | 'O' | 'x' | 'X' if !n.is_integer() => {
+                    bail!(args_span.error(&format!(
+                        "format verb %{verb} expects an integer. Got `{}` instead",
+                        n.format_decimal()
+                    )));
+                }
+                'c' => match n
+                    .as_u64()
+                    .and_then(|u| u32::try_from(u).ok())
+                    .and_then(char::from_u32)
+                {
+                    Some(c) => s += &spec.pad(c.to_string()),
                     _ => {
-                        bail!(args_span.error(
-                            format!("invalid value {} for format verb c.", f.format_decimal())
-                                .as_str()
-                        ))
+                        bail!(args_span.error(&format!(
+                            "invalid value {} for format verb c.",
+                            n.format_decimal()
+                        )))
+                    }
+                },
+                'b' | 'd' | 'o' | 'O' | 'x' | 'X' | 's' | 'v' if n.is_integer() => {
+                    let verb = if matches!(verb, 's' | 'v') { 'd' } else { verb };
+                    match integer_digits(n, verb) {
+                        Some((negative, digits)) => {
+                            s += &spec.format_integer(negative, digits, verb)
+                        }
+                        None => {
+                            bail!(args_span.error(&format!(
+                                "{} is too large for format verb %{verb}",
+                                n.format_decimal()
+                            )));
+                        }
                     }
                 }
-            }
-            ('d', Value::Number(f)) if f.is_integer() => {
-                let (sign, v) = get_sign_value(f);
-                s += sign;
-                s += apply_width(width, v.format_decimal()).as_str()
-            }
-            ('o', Value::Number(f)) if f.is_integer() => {
-                let (sign, v) = get_sign_value(f);
-                s += sign;
-                s += apply_width(width, "0O".to_owned() + &v.format_octal()).as_str()
-            }
-            ('O', Value::Number(f)) if f.is_integer() => {
-                let (sign, v) = get_sign_value(f);
-                s += sign;
-                s += apply_width(width, "0o".to_owned() + &v.format_octal()).as_str()
-            }
-            ('x', Value::Number(f)) if f.is_integer() => {
-                let (sign, v) = get_sign_value(f);
-                s += sign;
-                s += apply_width(width, v.format_hex()).as_str()
-            }
-            ('X', Value::Number(f)) if f.is_integer() => {
-                let (sign, v) = get_sign_value(f);
-                s += sign;
-                s += apply_width(width, v.format_big_hex()).as_str()
-            }
-            ('e', Value::Number(f)) => s += &f.format_scientific(),
-            ('E', Value::Number(f)) => s += &f.format_scientific().replace('e', "E"),
-            ('f' | 'F', Value::Number(f)) => {
-                s += &match width {
-                    Width::Decimals(d) => f.format_decimal_with_width(d as u32),
-                    _ => apply_width(width, f.format_decimal()),
-                }
-            }
-            ('g', Value::Number(f)) => {
-                let (sign, v) = get_sign_value(f);
-                let v = match v.as_f64() {
-                    Some(v) => v,
-                    _ => bail!(span.error("cannot print large float using g format specified")),
-                };
-                s += sign;
-                let bits = v.to_bits();
-                let exponent = (bits >> 52) & 0x7ff;
-                // TODO: what is large exponent?
-                if exponent > 32 {
-                    s += format!("{v:e}").as_str()
-                } else {
-                    s += format!("{v}").as_str()
+                's' => s += &spec.format_string(&n.format_decimal()),
+                _ => {
+                    // Numbers that cannot be represented exactly are approximated like in Go.
+                    let f = match n.as_f64() {
+                        Some(f) => f,
+                        None => n.format_scientific().parse().unwrap_or(f64::NAN),
+                    };
+                    if !f.is_finite() {
+                        bail!(args_span
+                            .error(&format!("cannot format {} as a float", n.format_decimal())));
+                    }
+                    s += &spec.format_float(f, verb)
                 }
-            }
-            ('G', Value::Number(f)) => {
-                let (sign, v) = get_sign_value(f);
-                let v = match v.as_f64() {
-                    Some(v) => v,
-                    _ => bail!("cannot print large float using g format specified"),
+            },
+            _ => {
+                let text = match arg {
+                    Value::String(sv) => sv.as_ref().to_string(),
+                    v => to_string(v, false),
                 };
-                s += sign;
-                let bits = v.to_bits();
-                let exponent = (bits >> 52) & 0x7ff;
-                // TODO: what is large exponent?
-                if exponent > 32 {
-                    s += format!("{v:E}").as_str()
-                } else {
-                    s += format!("{v}").as_str()
+                match verb {
+                    'v' if spec.sharp => s += &spec.pad(to_string(&Value::from(text), true)),
+                    's' | 'v' => s += &spec.format_string(&text),
+                    'x' | 'X' => {
+                        let bytes = match spec.precision {
+                            Some(p) => &text.as_bytes()[..p.min(text.len())],
+                            None => text.as_bytes(),
+                        };
+                        let mut hex = String::from(if spec.sharp { "0x" } else { "" });
+                        for b in bytes {
+                            hex += &format!("{b:02x}");
+                        }
+                        s += &spec.pad(match verb {
+                            'X' => hex.to_uppercase(),
+                            _ => hex,
+                        })
+                    }
+                    _ => {
+                        bail!(args_span.error(&format!(
+                            "format verb %{verb} expects a number. Got `{text}` instead"
+                        )));
+                    }
                 }
             }
-            (_, Value::Number(_)) => {
-                bail!(args_span.error(&format!("number specified for format verb {verb}.")));
-            }
-
-            ('+', _) if chars.next() == Some('v') => {
-                bail!(args_span.error("Go-syntax fields names format verm %#v is not supported."));
-            }
-            ('T', _) | ('#', _) | ('q', _) | ('p', _) => {
-                bail!(
-                    args_span.error("Go-syntax format verbs %#v. %q, %p and %T are not supported.")
-                );
-            }
-            _ => {}
         }
     }
 
@@ -420,6 +553,31 @@ fn sprintf(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) ->
     Ok(Value::String(s.into()))
 }
 
+// Accepts a single string or an array/set of strings.
+// Returns None if the argument is invalid in non-strict mode.
+fn ensure_string_or_collection<'a>(
+    name: &str,
+    param: &Expr,
+    arg: &'a Value,
+    strict: bool,
+) -> Result<Option<Vec<&'a str>>> {
+    match arg {
+        Value::String(s) => Ok(Some(vec![s.as_ref()])),
+        Value::Array(_) | Value::Set(_) => match ensure_string_collection(name, param, arg) {
+            Ok(c) => Ok(Some(c)),
+            Err(e) if strict => Err(e),
+            _ => Ok(None),
+        },
+        _ if strict => {
+            let span = param.span();
+            bail!(span.error(
+                format!("`{name}` expects string/array[string]/set[string] argument.").as_str()
+            ));
+        }
+        _ => Ok(None),
+    }
+}
+
 fn any_prefix_match(
     span: &Span,
     params: &[Ref<Expr>],
@@ -429,40 +587,11 @@ fn any_prefix_match(
     let name = "strings.any_prefix_match";
     ensure_args_count(span, name, params, args, 2)?;
 
-    let search = match &args[0] {
-        Value::String(s) => vec![s.as_ref()],
-        Value::Array(_) | Value::Set(_) => {
-            match ensure_string_collection(name, &params[0], &args[0]) {
-                Ok(c) => c,
-                Err(e) if strict => return Err(e),
-                _ => return Ok(Value::Undefined),
-            }
-        }
-        _ if strict => {
-            let span = params[0].span();
-            bail!(span.error(
-                format!("`{name}` expects string/array[string]/set[string] argument.").as_str()
-            ));
-        }
-        _ => return Ok(Value::Undefined),
+    let Some(search) = ensure_string_or_collection(name, &params[0], &args[0], strict)? else {
+        return Ok(Value::Undefined);
     };
-
-    let base = match &args[1] {
-        Value::String(s) => vec![s.as_ref()],
-        Value::Array(_) | Value::Set(_) => {
-            match ensure_string_collection(name, &params[1], &args[1]) {
-                Ok(c) => c,
-                Err(e) if strict => return Err(e),
-                _ => return Ok(Value::Undefined),
-            }
-        }
-        _ if strict => {
-            let span = params[0].span();
-            bail!(span.error(
-                format!("`{name}` expects string/array[string]/set[string] argument.").as_str()
-            ));
-        }
-        _ => return Ok(Value::Undefined),
+    let Some(base) = ensure_string_or_collection(name, &params[1], &args[1], strict)? else {
+        return Ok(Value::Undefined);
     };
 
     Ok(Value::Bool(
@@ -479,40 +608,11 @@ fn any_suffix_match(
     let name = "strings.any_suffix_match";
     ensure_args_count(span, name, params, args, 2)?;
 
-    let search = match &args[0] {
-        Value::String(s) => vec![s.as_ref()],
-        Value::Array(_) | Value::Set(_) => {
-            match ensure_string_collection(name, &params[0], &args[0]) {
-                Ok(c) => c,
-                Err(e) if strict => return Err(e),
-                _ => return Ok(Value::Undefined),
-            }
-        }
-        _ if strict => {
-            let span = params[0].span();
-            bail!(span.error(
-                format!("`{name}` expects string/array[string]/set[string] argument.").as_str()
-            ));
-        }
-        _ => return Ok(Value::Undefined),
+    let Some(search) = ensure_string_or_collection(name, &params[0], &args[0], strict)? else {
+        return Ok(Value::Undefined);
     };
-
-    let base = match &args[1] {
-        Value::String(s) => vec![s.as_ref()],
-        Value::Array(_) | Value::Set(_) => {
-            match ensure_string_collection(name, &params[1], &args[1]) {
-                Ok(c) => c,
-                Err(e) if strict => return Err(e),
-                _ => return Ok(Value::Undefined),
-            }
-        }
-        _ if strict => {
-            let span = params[0].span();
-            bail!(span.error(
-                format!("`{name}` expects string/array[string]/set[string] argument.").as_str()
-            ));
-        }
-        _ => return Ok(Value::Undefined),
+    let Some(base) = ensure_string_or_collection(name, &params[1], &args[1], strict)? else {
+        return Ok(Value::Undefined);
     };
 
     Ok(Value::Bool(
@@ -530,15 +630,15 @@ fn strings_count(
     ensure_args_count(span, name, params, args, 2)?;
 
     let search = ensure_string(name, &params[0], &args[0])?;
-    let substring = ensure_string(name, &params[0], &args[1])?;
-
-    Ok(Value::from(
-        search
-            .as_bytes()
-            .windows(substring.len())
-            .filter(|&w| w == substring.as_bytes())
-            .count(),
-    ))
+    let substring = ensure_string(name, &params[1], &args[1])?;
+
+    // Like Go's strings.Count, an empty substring matches before each character and at the end.
+    if substring.is_empty() {
+        return Ok(Value::from(search.chars().count() + 1));
+    }
+
+    // Occurrences are non-overlapping.
+    Ok(Value::from(search.matches(substring.as_ref()).count()))
 }
 
 fn startswith(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Result<Value> {
@@ -550,17 +650,16 @@ fn startswith(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool)
 }
 
 fn replace_n(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Result<Value> {
-    let name = "trim";
+    let name = "strings.replace_n";
     ensure_args_count(span, name, params, args, 2)?;
     let obj = ensure_object(name, &params[0], args[0].clone())?;
-    let mut s = ensure_string(name, &params[1], &args[1])?;
+    let s = ensure_string(name, &params[1], &args[1])?;
 
     let span = params[0].span();
-    for item in obj.as_ref().iter() {
+    let mut patterns = Vec::with_capacity(obj.len());
+    for item in obj.iter() {
         match item {
-            (Value::String(k), Value::String(v)) => {
-                s = s.replace(k.as_ref(), v.as_ref()).into();
-            }
+            (Value::String(k), Value::String(v)) => patterns.push((k.as_ref(), v.as_ref())),
             _ => {
                 bail!(span.error(
                     format!("`{name}` expects string keys and values in pattern object.").as_str()
@@ -569,11 +668,41 @@ fn replace_n(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -
         }
     }
 
-    Ok(Value::String(s.clone()))
+    // Like Go's strings.Replacer, which OPA uses, replacement is a single pass over the
+    // string. At each position the first matching pattern in key order wins, and replaced
+    // text is never matched again. E.g. {"a": "b", "b": "c"} turns "ab" into "bc" and
+    // {"a": "1", "ab": "2"} turns "ab" into "1b".
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s.as_ref();
+    let mut prev_match_empty = false;
+    loop {
+        // An empty pattern matches once at each position.
+        let matched = patterns
+            .iter()
+            .find(|(old, _)| rest.starts_with(old) && !(old.is_empty() && prev_match_empty));
+        prev_match_empty = false;
+        match matched {
+            Some((old, new)) => {
+                result.push_str(new);
+                rest = &rest[old.len()..];
+                prev_match_empty = old.is_empty();
+            }
+            None => {
+                let mut chars = rest.chars();
+                match chars.next() {
+                    Some(c) => result.push(c),
+                    None => break,
+                }
+                rest = chars.as_str();
+            }
+        }
+    }
+
+    Ok(Value::String(result.into()))
 }
 
 fn reverse(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Result<Value> {
-    let name = "reverse";
+    let name = "strings.reverse";
     ensure_args_count(span, name, params, args, 1)?;
     let s = ensure_string(name, &params[0], &args[0])?;
     Ok(Value::String(s.chars().rev().collect::<String>().into()))
@@ -11,8 +11,8 @@ use crate::*;
 use anyhow::{bail, Result};
 
 use chrono::{
-    DateTime, Datelike, Days, FixedOffset, Local, Months, SecondsFormat, TimeZone, Timelike, Utc,
-    Weekday,
+    DateTime, Datelike, Days, FixedOffset, Local, NaiveDate, SecondsFormat, TimeZone, Timelike,
+    Utc, Weekday,
 };
 use chrono_tz::Tz;
 
@@ -41,28 +41,31 @@ fn add_date(span: &Span, params: &[Ref<Expr>], args: &[Value], strict: bool) ->
     let months = ensure_i32(name, &params[2], &args[2])?;
     let days = ensure_i32(name, &params[3], &args[3])?;
 
-    let Some(new_year) = datetime.year().checked_add(years) else {
+    // Like Go's time.AddDate, the month and day are normalized rather than clamped,
+    // i.e. adding one month to January 31st yields March 2nd (or 3rd).
+    let local = datetime.naive_local();
+    let total_months =
+        (local.year() as i64 + years as i64) * 12 + local.month0() as i64 + months as i64;
+    let Ok(year) = i32::try_from(total_months.div_euclid(12)) else {
         return Ok(Value::Undefined);
     };
+    let month = total_months.rem_euclid(12) as u32 + 1;
+    let days = local.day0() as i64 + days as i64;
 
-    datetime
-        .with_year(new_year)
+    NaiveDate::from_ymd_opt(year, month, 1)
         .and_then(|d| {
-            let rhs = Months::new(months.unsigned_abs());
-            if months >= 0 {
-                d.checked_add_months(rhs)
-            } else {
-                d.checked_sub_months(rhs)
-            }
-        })
-        .and_then(|d| {
-            let rhs = Days::new(days.unsigned_abs() as u64);
+            let rhs = Days::new(days.unsigned_abs());
             if days >= 0 {
                 d.checked_add_days(rhs)
             } else {
                 d.checked_sub_days(rhs)
             }
         })
+        .and_then(|d| {
+            d.and_time(local.time())
+                .and_local_timezone(*datetime.offset())
+                .single()
+        })
         .map_or(Ok(Value::Undefined), |d| {
             safe_timestamp_nanos(span, strict, d.timestamp_nanos_opt())
         })
@@ -89,7 +92,7 @@ fn date(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Res
     let (datetime, _) = parse_epoch(name, &params[0], &args[0])?;
 
     Ok(Vec::from([
-        (datetime.year() as u64).into(),
+        (datetime.year() as i64).into(),
         (datetime.month() as u64).into(),
         (datetime.day() as u64).into(),
     ])
@@ -173,7 +176,17 @@ fn parse_rfc3339_ns(
 
     let value = ensure_string(name, &params[0], &args[0])?;
 
-    let datetime = DateTime::parse_from_rfc3339(&value)?;
+    let datetime = DateTime::parse_from_rfc3339(&value).map_err(|e| {
+        params[0]
+            .span()
+            .error(&format!("could not parse rfc3339 time\nCaused by\n{e}"))
+    })?;
+    // chrono accepts leap seconds (23:59:60) whereas Go (and hence OPA) rejects them.
+    if datetime.nanosecond() >= 1_000_000_000 {
+        bail!(params[0]
+            .span()
+            .error("could not parse rfc3339 time\nCaused by\nsecond out of range"));
+    }
     safe_timestamp_nanos(span, strict, datetime.timestamp_nanos_opt())
 }
 
@@ -247,10 +260,10 @@ fn parse_epoch(
                     "UTC" | "" => Utc.timestamp_nanos(ns).fixed_offset(),
                     "Local" => Local.timestamp_nanos(ns).fixed_offset(),
                     _ => {
-                        let tz: Tz = match tz.parse() {
-                            Ok(tz) => tz,
-                            Err(e) => bail!(e),
-                        };
+                        let tz: Tz = tz.parse().map_err(|_| {
+                            arg.span()
+                                .error(&format!("`{fcn}` got unknown time zone `{tz}`"))
+                        })?;
                         tz.timestamp_nanos(ns).fixed_offset()
                     }
                 };
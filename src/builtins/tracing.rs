@@ -18,11 +18,8 @@ pub fn register(m: &mut builtins::BuiltinsMap<&'static str, builtins::BuiltinFcn
 fn trace(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Result<Value> {
     let name = "trace";
     ensure_args_count(span, name, params, args, 1)?;
-    let msg = ensure_string(name, &params[0], &args[0])?;
+    ensure_string(name, &params[0], &args[0])?;
 
-    // Unlike rego, trace returns a string instead of bool.
-    // The interpreter accumulates the traces.
-    // TODO: Stateful bultins can pass in a state that would allow capturing
-    // the traces in the state.
-    Ok(Value::String(msg))
+    // The interpreter records the message if tracing or explanations are enabled.
+    Ok(Value::Bool(true))
 }
@@ -108,7 +108,9 @@ fn parse(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) -> Re
         n.mul_assign(&Number::two_pow(e)?)?;
         Ok(Value::from(n))
     } else {
-        return Ok(Value::Undefined);
+        bail!(params[0]
+            .span()
+            .error(&format!("unit `{suffix}` not recognized")))
     }
 }
 
@@ -188,6 +190,8 @@ fn parse_bytes(span: &Span, params: &[Ref<Expr>], args: &[Value], strict: bool)
         n.mul_assign(&Number::ten_pow(e)?)?;
         Ok(Value::from(n.round()))
     } else {
-        Ok(Value::Undefined)
+        bail!(params[0]
+            .span()
+            .error(&format!("byte unit `{suffix}` not recognized")))
     }
 }
@@ -93,6 +93,24 @@ fn rfc4122(span: &Span, params: &[Ref<Expr>], args: &[Value], _strict: bool) ->
     Ok(Value::String(uuid.to_string().into()))
 }
 
+/// uuid.rfc4122 drawing its random bytes from the given generator.
+///
+/// The interpreter uses this with a seeded generator when the engine has a seed.
+#[cfg(feature = "std")]
+pub fn rfc4122_with_rng<R: rand::Rng>(
+    rng: &mut R,
+    span: &Span,
+    params: &[Ref<Expr>],
+    args: &[Value],
+) -> Result<Value> {
+    let name = "uuid.rfc4122";
+    ensure_args_count(span, name, params, args, 1)?;
+    ensure_string(name, &params[0], &args[0])?;
+
+    let uuid = uuid::Builder::from_random_bytes(rng.gen()).into_uuid();
+    Ok(Value::String(uuid.to_string().into()))
+}
+
 fn mac_vars(b: u8) -> &'static str {
     if b & 0b11 == 0b11 {
         return "local:multicast";
@@ -9,9 +9,9 @@ use crate::scheduler::*;
 use crate::utils::gather_functions;
 use crate::value::*;
 use crate::*;
-use crate::{Extension, QueryResults};
+use crate::{Diagnostic, Extension, ModuleResolver, PartialResult, QueryResults, TraceEvent};
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 
 /// The Rego evaluation engine.
 ///
@@ -21,6 +21,9 @@ pub struct Engine {
     interpreter: Interpreter,
     prepared: bool,
     rego_v1: bool,
+    strict: bool,
+    module_resolver: Option<Rc<dyn ModuleResolver>>,
+    value_limits: ValueLimits,
 }
 
 /// Create a default engine.
@@ -38,6 +41,9 @@ impl Engine {
             interpreter: Interpreter::new(),
             prepared: false,
             rego_v1: false,
+            strict: false,
+            module_resolver: None,
+            value_limits: ValueLimits::default(),
         }
     }
 
@@ -66,6 +72,68 @@ impl Engine {
         self.rego_v1 = rego_v1;
     }
 
+    /// Turn strict compilation on/off.
+    ///
+    /// In strict mode, preparing the engine for evaluation fails if any policy has unused
+    /// local variables or unused imports. Unsafe variables are always reported as errors.
+    /// Use [`Engine::get_strict_diagnostics`] to obtain the individual problems.
+    ///
+    /// ```
+    /// # use regorus::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut engine = Engine::new();
+    /// engine.add_policy(
+    ///    "test.rego".to_string(),
+    ///    r#"
+    ///    package test
+    ///    import data.roles
+    ///
+    ///    x := 5
+    ///    "#.to_string())?;
+    ///
+    /// assert!(engine.eval_rule("data.test.x".to_string()).is_ok());
+    ///
+    /// engine.set_strict(true);
+    /// assert!(engine.eval_rule("data.test.x".to_string()).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+        self.prepared = false;
+    }
+
+    /// Get the problems reported by strict compilation.
+    ///
+    /// The problems are reported irrespective of whether strict mode is turned on.
+    ///
+    /// ```
+    /// # use regorus::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut engine = Engine::new();
+    /// engine.add_policy(
+    ///    "test.rego".to_string(),
+    ///    r#"
+    ///    package test
+    ///    import rego.v1
+    ///
+    ///    allow if {
+    ///      y := input.y
+    ///      input.x > 1
+    ///    }
+    ///    "#.to_string())?;
+    ///
+    /// let diagnostics = engine.get_strict_diagnostics()?;
+    /// assert_eq!(diagnostics.len(), 1);
+    /// assert_eq!(diagnostics[0].message, "assigned var `y` unused");
+    /// assert_eq!(diagnostics[0].span.line, 6);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_strict_diagnostics(&self) -> Result<Vec<Diagnostic>> {
+        check_strict(&self.modules)
+    }
+
     /// Add a policy.
     ///
     /// The policy file will be parsed and converted to AST representation.
@@ -96,10 +164,7 @@ impl Engine {
         let source = Source::from_contents(path, rego)?;
         let mut parser = self.make_parser(&source)?;
         let module = Ref::new(parser.parse()?);
-        self.modules.push(module.clone());
-        // if policies change, interpreter needs to be prepared again
-        self.prepared = false;
-        Interpreter::get_path_string(&module.package.refr, Some("data"))
+        self.add_module(module)
     }
 
     /// Add a policy from a given file.
@@ -127,10 +192,93 @@ impl Engine {
         let source = Source::from_file(path)?;
         let mut parser = self.make_parser(&source)?;
         let module = Ref::new(parser.parse()?);
-        self.modules.push(module.clone());
+        self.add_module(module)
+    }
+
+    fn add_module(&mut self, module: Ref<Module>) -> Result<String> {
+        let package = Interpreter::get_path_string(&module.package.refr, Some("data"))?;
+        // Resolved modules are added only once all imports have been resolved, so that a
+        // failure leaves the engine unchanged.
+        let mut resolved = vec![];
+        if let Some(resolver) = self.module_resolver.clone() {
+            let mut loading = vec![package.clone()];
+            self.resolve_imports(resolver.as_ref(), &module, &mut loading, &mut resolved)?;
+        }
+        self.modules.extend(resolved);
+        self.modules.push(module);
         // if policies change, interpreter needs to be prepared again
         self.prepared = false;
-        Interpreter::get_path_string(&module.package.refr, Some("data"))
+        Ok(package)
+    }
+
+    fn is_package_loaded(&self, resolved: &[Ref<Module>], path: &str) -> Result<bool> {
+        for m in self.modules.iter().chain(resolved) {
+            let package = Interpreter::get_path_string(&m.package.refr, Some("data"))?;
+            if path == package
+                || path.starts_with(&(package.clone() + "."))
+                || package.starts_with(&(path.to_string() + "."))
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    // Load the packages imported by the module (and transitively by the loaded modules)
+    // via the resolver. `loading` is the chain of packages whose imports are being resolved.
+    // Loaded modules are appended to `resolved` after their own dependencies.
+    fn resolve_imports(
+        &self,
+        resolver: &dyn ModuleResolver,
+        module: &Ref<Module>,
+        loading: &mut Vec<String>,
+        resolved: &mut Vec<Ref<Module>>,
+    ) -> Result<()> {
+        for import in &module.imports {
+            let path = match Interpreter::get_path_string(&import.refr, None) {
+                Ok(path) if path.starts_with("data.") => path,
+                _ => continue,
+            };
+            if self.is_package_loaded(resolved, &path)? {
+                continue;
+            }
+
+            // The import refers either to a package or to a rule within a package.
+            let comps: Vec<&str> = path.split('.').collect();
+            for n in (2..comps.len() + 1).rev() {
+                let package = comps[..n].join(".");
+                if let Some(idx) = loading.iter().position(|p| p == &package) {
+                    let cycle = loading[idx..].join(" -> ");
+                    bail!(import
+                        .span
+                        .error(&format!("circular import: {cycle} -> {package}")));
+                }
+            }
+
+            for n in (2..comps.len() + 1).rev() {
+                let package = comps[..n].join(".");
+                let Some((file, rego)) = resolver.resolve(&package)? else {
+                    continue;
+                };
+
+                let source = Source::from_contents(file, rego)?;
+                let loaded = Ref::new(self.make_parser(&source)?.parse()?);
+                let loaded_package =
+                    Interpreter::get_path_string(&loaded.package.refr, Some("data"))?;
+                if loaded_package != package {
+                    bail!(import.span.error(&format!(
+                        "module resolver returned package `{loaded_package}` for `{package}`"
+                    )));
+                }
+
+                loading.push(package);
+                self.resolve_imports(resolver, &loaded, loading, resolved)?;
+                loading.pop();
+                resolved.push(loaded);
+                break;
+            }
+        }
+        Ok(())
     }
 
     /// Get the list of packages defined by loaded policies.
@@ -239,11 +387,50 @@ impl Engine {
         self.interpreter.set_input(input);
     }
 
+    /// Set the input document from JSON.
+    ///
+    /// The document is subject to the limits set via [`Engine::set_value_limits`].
     pub fn set_input_json(&mut self, input_json: &str) -> Result<()> {
-        self.set_input(Value::from_json_str(input_json)?);
+        self.set_input(Value::from_json_str_with_limits(
+            input_json,
+            &self.value_limits,
+        )?);
         Ok(())
     }
 
+    /// Set limits on the size of input and data documents.
+    ///
+    /// Documents passed to [`Engine::set_input_json`], [`Engine::add_data`] and
+    /// [`Engine::add_data_json`] that are nested deeper or contain more values than allowed
+    /// are rejected with an error. JSON documents are rejected while being parsed, before
+    /// they are fully allocated. By default, there are no limits.
+    ///
+    /// [`Engine::set_input`] takes an already constructed [`Value`] and is not checked.
+    ///
+    /// ```
+    /// # use regorus::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut engine = Engine::new();
+    /// engine.set_value_limits(ValueLimits {
+    ///     max_depth: Some(3),
+    ///     max_nodes: Some(100),
+    /// });
+    ///
+    /// engine.set_input_json(r#"{ "user": { "roles": ["admin"] } }"#)?;
+    ///
+    /// // Too deep.
+    /// assert!(engine.set_input_json(r#"{ "a": { "b": { "c": {} } } }"#).is_err());
+    ///
+    /// // Too many values.
+    /// let wide = format!("[{}]", vec!["0"; 100].join(","));
+    /// assert!(engine.add_data_json(&format!(r#"{{ "wide": {wide} }}"#)).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_value_limits(&mut self, limits: ValueLimits) {
+        self.value_limits = limits;
+    }
+
     /// Clear the data document.
     ///
     /// The data document will be reset to an empty object.
@@ -302,10 +489,113 @@ impl Engine {
         if data.as_object().is_err() {
             bail!("data must be object");
         }
+        data.check_limits(&self.value_limits)?;
         self.prepared = false;
         self.interpreter.get_init_data_mut().merge(data)
     }
 
+    /// Set the value at the given path within the data document.
+    ///
+    /// The path is a JSON pointer (e.g. `/servers/0/ports`) relative to `data`.
+    /// Missing intermediate objects are created. Existing array elements can be replaced
+    /// by index. An empty path replaces the whole data document, which must be an object.
+    ///
+    /// Unlike [`Engine::add_data`], only the data document is updated; the compiled
+    /// policies are reused by subsequent evaluations. The resulting data document is subject
+    /// to the limits set via [`Engine::set_value_limits`]; on error, the data is unchanged.
+    ///
+    /// ```
+    /// # use regorus::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut engine = Engine::new();
+    /// engine.add_data(Value::from_json_str(r#"{ "limits": { "cpu": 2, "memory": 4 } }"#)?)?;
+    ///
+    /// engine.patch_data("/limits/cpu", Value::from(8))?;
+    /// engine.patch_data("/owners/team", Value::from("infra"))?;
+    ///
+    /// assert_eq!(
+    ///   engine.get_data(),
+    ///   Value::from_json_str(r#"{
+    ///     "limits": { "cpu": 8, "memory": 4 },
+    ///     "owners": { "team": "infra" }
+    ///   }"#)?
+    /// );
+    ///
+    /// // Paths must be JSON pointers.
+    /// assert!(engine.patch_data("limits.cpu", Value::from(8)).is_err());
+    ///
+    /// // Scalars cannot be indexed.
+    /// assert!(engine.patch_data("/limits/cpu/max", Value::from(8)).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn patch_data(&mut self, path: &str, value: Value) -> Result<()> {
+        let path = Self::parse_data_path(path)?;
+        if path.is_empty() && value.as_object().is_err() {
+            bail!("data must be object");
+        }
+        let mut data = self.interpreter.get_init_data().clone();
+        *data.make_or_get_value_at_path_mut(&path)? = value;
+        // Check the whole document so that repeated patches cannot grow it past the limits.
+        data.check_limits(&self.value_limits)?;
+        self.set_init_data(data)
+    }
+
+    /// Remove the value at the given path within the data document.
+    ///
+    /// The path is a JSON pointer relative to `data`. Removing an array element shifts the
+    /// subsequent elements. Returns whether a value was removed.
+    ///
+    /// ```
+    /// # use regorus::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut engine = Engine::new();
+    /// engine.add_data(Value::from_json_str(r#"{ "users": { "alice": {}, "bob": {} } }"#)?)?;
+    ///
+    /// assert!(engine.remove_data("/users/bob")?);
+    /// assert!(!engine.remove_data("/users/carol")?);
+    ///
+    /// assert_eq!(engine.get_data(), Value::from_json_str(r#"{ "users": { "alice": {} } }"#)?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn remove_data(&mut self, path: &str) -> Result<bool> {
+        let path = Self::parse_data_path(path)?;
+        if path.is_empty() {
+            bail!("cannot remove the data document; use `clear_data` instead");
+        }
+
+        let mut data = self.interpreter.get_init_data().clone();
+        if !data.remove_value_at_path(&path)? {
+            return Ok(false);
+        }
+        self.set_init_data(data)?;
+        Ok(true)
+    }
+
+    fn parse_data_path(path: &str) -> Result<Vec<String>> {
+        if path.is_empty() {
+            return Ok(vec![]);
+        }
+        let Some(path) = path.strip_prefix('/') else {
+            bail!("data path `{path}` must be a JSON pointer starting with `/`");
+        };
+        Ok(path
+            .split('/')
+            .map(|p| p.replace("~1", "/").replace("~0", "~"))
+            .collect())
+    }
+
+    fn set_init_data(&mut self, data: Value) -> Result<()> {
+        self.interpreter.set_init_data(data);
+        // Policy analysis does not depend on data. Only refresh the document that
+        // with-modifiers are applied to.
+        if self.prepared {
+            self.interpreter.init_with_document()?;
+        }
+        Ok(())
+    }
+
     /// Get the data document.
     ///
     /// The returned value is the data document that has been constructed using
@@ -339,8 +629,14 @@ impl Engine {
         self.interpreter.get_init_data().clone()
     }
 
+    /// Add data document from JSON.
+    ///
+    /// The document is subject to the limits set via [`Engine::set_value_limits`].
     pub fn add_data_json(&mut self, data_json: &str) -> Result<()> {
-        self.add_data(Value::from_json_str(data_json)?)
+        self.add_data(Value::from_json_str_with_limits(
+            data_json,
+            &self.value_limits,
+        )?)
     }
 
     /// Set whether builtins should raise errors strictly or not.
@@ -360,6 +656,48 @@ impl Engine {
         &self.modules
     }
 
+    #[doc(hidden)]
+    pub fn is_prepared(&self) -> bool {
+        self.prepared
+    }
+
+    /// Compile the loaded policies and data for evaluation.
+    ///
+    /// Policies are parsed when they are added. Before the first evaluation, they are
+    /// additionally analyzed and their rules and functions gathered. [`Engine::prepare`]
+    /// performs that step eagerly so that clones of the engine can be evaluated against
+    /// different inputs without parsing or analyzing the policies again. Changing policies
+    /// or data afterwards requires preparing again, which happens automatically on the next
+    /// evaluation.
+    ///
+    /// ```
+    /// # use regorus::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut engine = Engine::new();
+    /// engine.add_policy(
+    ///   "policy.rego".to_string(),
+    ///   r#"
+    ///   package authz
+    ///   import rego.v1
+    ///
+    ///   allow if input.user == "admin"
+    ///   "#.to_string())?;
+    /// engine.prepare()?;
+    ///
+    /// // Each request gets a cheap clone of the prepared engine.
+    /// for (user, expected) in [("admin", true), ("guest", false)] {
+    ///   let mut request = engine.clone();
+    ///   request.set_input(Value::from_json_str(&format!(r#"{{"user": "{user}"}}"#))?);
+    ///   let allow = request.eval_rule("data.authz.allow".to_string())?;
+    ///   assert_eq!(allow == Value::from(true), expected);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn prepare(&mut self) -> Result<()> {
+        self.prepare_for_eval(false)
+    }
+
     /// Evaluate specified rule(s).
     ///
     /// [`Engine::eval_rule`] is often faster than [`Engine::eval_query`] and should be preferred if
@@ -469,8 +807,67 @@ impl Engine {
         )
     }
 
+    /// Partially evaluate a Rego query.
+    ///
+    /// The references listed in `unknowns` (e.g. `input.user`) are treated as unknown. Statements
+    /// that do not depend on the unknowns are evaluated; the rest are returned as residual
+    /// queries in which known values have been substituted. Rules that the query depends upon
+    /// are inlined into the residual queries.
+    ///
+    /// Only conjunctions of expressions, `not` expressions, assignments and rule references are
+    /// supported when they involve unknowns; an error is raised for other constructs.
+    ///
+    /// ```
+    /// # use regorus::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut engine = Engine::new();
+    /// engine.add_policy(
+    ///    "test.rego".to_string(),
+    ///    r#"
+    ///    package test
+    ///    import rego.v1
+    ///
+    ///    limit := 5
+    ///
+    ///    allow if input.x > limit
+    ///    allow if input.admin == true
+    ///    "#.to_string())?;
+    ///
+    /// let result = engine.partial_eval("data.test.allow".to_string(), vec!["input".to_string()])?;
+    /// assert_eq!(result.queries, [["input.x > 5"], ["input.admin == true"]]);
+    ///
+    /// // Queries that do not depend on the unknowns are evaluated.
+    /// let result = engine.partial_eval("data.test.limit > 2".to_string(), vec!["input".to_string()])?;
+    /// assert_eq!(result.queries, [Vec::<String>::new()]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn partial_eval(&mut self, query: String, unknowns: Vec<String>) -> Result<PartialResult> {
+        self.prepare_for_eval(false)?;
+        self.interpreter.clean_internal_evaluation_state();
+
+        self.interpreter.create_rule_prefixes()?;
+        let query_module = {
+            let source = Source::from_contents(
+                "<query_module.rego>".to_owned(),
+                "package __internal_query_module".to_owned(),
+            )?;
+            Ref::new(Parser::new(&source)?.parse()?)
+        };
+
+        let query_source = Source::from_contents("<query.rego>".to_string(), query)?;
+        let mut parser = self.make_parser(&query_source)?;
+        let query_node = parser.parse_user_query()?;
+        let queries = self
+            .interpreter
+            .partial_eval_query(&query_module, &query_node, &unknowns)?;
+        Ok(PartialResult { queries })
+    }
+
     /// Evaluate a Rego query that produces a boolean value.
     ///
+    /// An error is raised if the query is undefined, produces a non-boolean value or
+    /// produces multiple results.
     ///
     /// This function should be preferred over [`Engine::eval_query`] if just a `true`/`false`
     /// value is desired instead of [`QueryResults`].
@@ -498,14 +895,67 @@ impl Engine {
     pub fn eval_bool_query(&mut self, query: String, enable_tracing: bool) -> Result<bool> {
         let results = self.eval_query(query, enable_tracing)?;
         match results.result.len() {
-            0 => bail!("query did not produce any values"),
+            0 => bail!("query is undefined"),
             1 if results.result[0].expressions.len() == 1 => {
-                results.result[0].expressions[0].value.as_bool().copied()
+                match &results.result[0].expressions[0].value {
+                    Value::Bool(b) => Ok(*b),
+                    v => bail!("query produced non-boolean value `{v}`"),
+                }
             }
-            _ => bail!("query produced more than one value"),
+            _ => bail!("query produced multiple results"),
         }
     }
 
+    /// Evaluate a Rego query and deserialize its value into `T`.
+    ///
+    /// The query must produce exactly one value. An error is raised if the query is undefined,
+    /// produces multiple results or if the value does not match the shape of `T`.
+    ///
+    /// ```
+    /// # use regorus::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// #[derive(serde::Deserialize)]
+    /// struct Decision {
+    ///     allowed: bool,
+    ///     reasons: Vec<String>,
+    /// }
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.add_policy(
+    ///    "test.rego".to_string(),
+    ///    r#"
+    ///    package test
+    ///    decision := { "allowed": false, "reasons": ["not an admin"] }
+    ///    "#.to_string())?;
+    ///
+    /// let decision: Decision = engine.eval_query_as("data.test.decision".to_string())?;
+    /// assert!(!decision.allowed);
+    /// assert_eq!(decision.reasons, ["not an admin"]);
+    ///
+    /// // The shape of the value must match.
+    /// assert!(engine.eval_query_as::<Vec<String>>("data.test.decision".to_string()).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn eval_query_as<T: serde::de::DeserializeOwned>(&mut self, query: String) -> Result<T> {
+        let results = self.eval_query(query, false)?;
+        let value = match results.result.len() {
+            0 => bail!("query is undefined"),
+            1 if results.result[0].expressions.len() == 1 => {
+                &results.result[0].expressions[0].value
+            }
+            _ => bail!("query produced multiple results"),
+        };
+
+        let json = serde_json::to_value(value).map_err(anyhow::Error::msg)?;
+        serde_json::from_value(json).map_err(|e| {
+            anyhow!(
+                "query result does not match `{}`: {e}",
+                core::any::type_name::<T>()
+            )
+        })
+    }
+
     /// Evaluate an `allow` query.
     ///
     /// This is a wrapper over [`Engine::eval_bool_query`] that returns true only if the
@@ -593,6 +1043,14 @@ impl Engine {
             let analyzer = Analyzer::new();
             let schedule = analyzer.analyze(&self.modules)?;
 
+            if self.strict {
+                let diagnostics = check_strict(&self.modules)?;
+                if !diagnostics.is_empty() {
+                    let messages: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+                    bail!(messages.join("\n"));
+                }
+            }
+
             self.interpreter.set_schedule(Some(schedule));
             self.interpreter.set_modules(&self.modules);
 
@@ -678,6 +1136,9 @@ impl Engine {
     /// * `nargs`: The number of arguments the builtin takes.
     /// * `extension`: The [`Extension`] instance.
     ///
+    /// Extensions cannot shadow existing builtins; registering an extension
+    /// whose path matches a builtin (e.g. `count`) is an error.
+    ///
     /// ```rust
     /// # use regorus::*;
     /// # use anyhow::{bail, Result};
@@ -735,6 +1196,11 @@ impl Engine {
     ///   Ok(Value::Undefined)
     /// })).is_err());
     ///
+    /// // Builtins cannot be overridden by extensions.
+    /// assert!(engine.add_extension("count".to_string(), 1, Box::new(|_:Vec<Value>| {
+    ///   Ok(Value::Undefined)
+    /// })).is_err());
+    ///
     /// // Extensions don't support out-parameter syntax.
     /// engine.add_policy(
     ///   "policy.rego".to_string(),
@@ -821,6 +1287,206 @@ impl Engine {
         self.interpreter.clear_coverage_data()
     }
 
+    /// Limit the number of steps an evaluation may take.
+    ///
+    /// Every expression evaluated counts as a step. An evaluation that exceeds the budget is
+    /// aborted with a [`BudgetExceeded`] error pointing at the expression being evaluated.
+    /// This bounds the time spent on untrusted policies, e.g. ones with large comprehensions.
+    /// The count restarts with every evaluation. Pass `None` to remove the limit.
+    ///
+    /// ```rust
+    /// # use regorus::*;
+    /// # use anyhow::{bail, Result};
+    /// # fn main() -> Result<()> {
+    /// let mut engine = Engine::new();
+    /// engine.add_policy(
+    ///    "test.rego".to_string(),
+    ///    "package test\n pairs := count([[x, y] | x := numbers.range(1, 1000)[_]; y := numbers.range(1, 1000)[_]])".to_string())?;
+    ///
+    /// engine.set_max_steps(Some(100_000));
+    /// let err = engine.eval_rule("data.test.pairs".to_string()).unwrap_err();
+    /// let budget = err.downcast_ref::<BudgetExceeded>().expect("budget exceeded");
+    /// assert_eq!(budget.max_steps, 100_000);
+    /// assert_eq!(budget.line, 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_max_steps(&mut self, max_steps: Option<u64>) {
+        self.interpreter.set_max_steps(max_steps);
+    }
+
+    /// Set a flag that cancels evaluations when set.
+    ///
+    /// The flag is checked before every expression is evaluated. Once it is set, the ongoing
+    /// evaluation, as well as any subsequent one, fails with a [`Cancelled`] error until the
+    /// flag is cleared again. Unlike [`Engine::set_max_steps`], cancellation is triggered
+    /// externally, e.g. from another thread when a client disconnects.
+    ///
+    /// ```rust
+    /// # use regorus::*;
+    /// # use anyhow::{bail, Result};
+    /// # use std::sync::{atomic::{AtomicBool, Ordering}, Arc};
+    /// # fn main() -> Result<()> {
+    /// let mut engine = Engine::new();
+    /// engine.add_policy(
+    ///    "test.rego".to_string(),
+    ///    "package test\n x := 1".to_string())?;
+    ///
+    /// let flag = Arc::new(AtomicBool::new(false));
+    /// engine.set_cancellation_flag(Some(flag.clone()));
+    /// assert_eq!(engine.eval_rule("data.test.x".to_string())?, Value::from(1u64));
+    ///
+    /// flag.store(true, Ordering::Relaxed);
+    /// let err = engine.eval_rule("data.test.x".to_string()).unwrap_err();
+    /// assert!(err.downcast_ref::<Cancelled>().is_some());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_cancellation_flag(
+        &mut self,
+        flag: Option<alloc::sync::Arc<core::sync::atomic::AtomicBool>>,
+    ) {
+        self.interpreter.set_cancellation_flag(flag);
+    }
+
+    #[cfg(feature = "time")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+    /// Pin the value returned by `time.now_ns`.
+    ///
+    /// Like OPA, `time.now_ns` reads the wall-clock once per evaluation and returns the
+    /// identical timestamp for every call within that evaluation. Pinning the clock makes
+    /// every evaluation use `now_ns` instead, which is useful for deterministic tests.
+    /// Pass `None` to go back to the wall-clock.
+    ///
+    /// ```rust
+    /// # use regorus::*;
+    /// # use anyhow::{bail, Result};
+    /// # fn main() -> Result<()> {
+    /// let mut engine = Engine::new();
+    /// engine.add_policy(
+    ///    "test.rego".to_string(),
+    ///    "package test\n now := time.now_ns()".to_string())?;
+    ///
+    /// engine.set_clock(Some(1_700_000_000_000_000_000));
+    /// let now = engine.eval_rule("data.test.now".to_string())?;
+    /// assert_eq!(now, Value::from(1_700_000_000_000_000_000i64));
+    ///
+    /// // Use the wall-clock again.
+    /// engine.set_clock(None);
+    /// let now = engine.eval_rule("data.test.now".to_string())?;
+    /// assert!(now > Value::from(1_700_000_000_000_000_000i64));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_clock(&mut self, now_ns: Option<i64>) {
+        self.interpreter.set_clock(now_ns);
+    }
+
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    /// Seed the random number generator used by `rand.intn` and `uuid.rfc4122`.
+    ///
+    /// Like OPA, `rand.intn` and `uuid.rfc4122` return the same value for the same
+    /// arguments within an evaluation regardless of the seed. With a seed, every evaluation additionally starts
+    /// from the same random sequence and hence produces the same values, which is useful
+    /// for tests. Pass `None` to use unpredictable values again.
+    ///
+    /// ```rust
+    /// # use regorus::*;
+    /// # use anyhow::{bail, Result};
+    /// # fn main() -> Result<()> {
+    /// let mut engine = Engine::new();
+    /// engine.add_policy(
+    ///    "test.rego".to_string(),
+    ///    "package test\n x := rand.intn(\"x\", 1000000)".to_string())?;
+    ///
+    /// engine.set_rand_seed(Some(42));
+    /// let x1 = engine.eval_rule("data.test.x".to_string())?;
+    /// let x2 = engine.eval_rule("data.test.x".to_string())?;
+    /// assert_eq!(x1, x2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_rand_seed(&mut self, seed: Option<u64>) {
+        self.interpreter.set_rand_seed(seed);
+    }
+
+    #[cfg(feature = "opa-runtime")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "opa-runtime")))]
+    /// Set the object returned by `opa.runtime`.
+    ///
+    /// The process environment is never exposed to policies. Instead the embedder decides
+    /// what `opa.runtime` returns, typically an object with `env` and `config` fields.
+    /// Without a runtime object, `opa.runtime` returns information about regorus along with
+    /// empty `env` and `config` objects. Pass `None` to restore the default.
+    ///
+    /// ```rust
+    /// # use regorus::*;
+    /// # use anyhow::{bail, Result};
+    /// # fn main() -> Result<()> {
+    /// let mut engine = Engine::new();
+    /// engine.add_policy(
+    ///    "test.rego".to_string(),
+    ///    "package test\n region := opa.runtime().env.REGION".to_string())?;
+    ///
+    /// engine.set_runtime(Some(Value::from_json_str(r#"{
+    ///   "env": { "REGION": "westus" },
+    ///   "config": { "labels": { "tier": "prod" } }
+    /// }"#)?))?;
+    /// assert_eq!(engine.eval_rule("data.test.region".to_string())?, Value::from("westus"));
+    ///
+    /// // The runtime must be an object.
+    /// assert!(engine.set_runtime(Some(Value::from(1))).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_runtime(&mut self, runtime: Option<Value>) -> Result<()> {
+        if let Some(runtime) = &runtime {
+            if runtime.as_object().is_err() {
+                bail!("runtime must be object");
+            }
+        }
+        self.interpreter.set_runtime(runtime);
+        Ok(())
+    }
+
+    #[cfg(feature = "net")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "net")))]
+    /// Set the [`Resolver`] used by `net.lookup_ip_addr`.
+    ///
+    /// No resolver is configured by default, in which case `net.lookup_ip_addr` raises
+    /// an error. Use [`SystemResolver`] to perform real DNS lookups, or a closure to stub
+    /// them out. Pass `None` to remove the resolver.
+    ///
+    /// ```rust
+    /// # use regorus::*;
+    /// # use anyhow::{bail, Result};
+    /// # fn main() -> Result<()> {
+    /// let mut engine = Engine::new();
+    /// engine.add_policy(
+    ///    "test.rego".to_string(),
+    ///    "package test\n addrs := net.lookup_ip_addr(\"example.com\")".to_string())?;
+    ///
+    /// // Without a resolver, the lookup fails.
+    /// assert!(engine.eval_rule("data.test.addrs".to_string()).is_err());
+    ///
+    /// engine.set_resolver(Some(Box::new(|name: &str| {
+    ///     match name {
+    ///         "example.com" => Ok(vec!["93.184.215.14".parse()?]),
+    ///         _ => bail!("unknown host {name}"),
+    ///     }
+    /// })));
+    /// let addrs = engine.eval_rule("data.test.addrs".to_string())?;
+    /// let mut expected = Value::new_set();
+    /// expected.as_set_mut()?.insert(Value::from("93.184.215.14"));
+    /// assert_eq!(addrs, expected);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_resolver(&mut self, resolver: Option<Box<dyn Resolver>>) {
+        self.interpreter.set_resolver(resolver);
+    }
+
     /// Gather output from print statements instead of emiting to stderr.
     ///
     /// See [`Engine::take_prints`].    
@@ -828,6 +1494,88 @@ impl Engine {
         self.interpreter.set_gather_prints(b);
     }
 
+    /// Set the [`ModuleResolver`] used to load imported packages on demand.
+    ///
+    /// When a policy is added, each `import data...` that refers to a package that has not been
+    /// loaded yet is looked up via the resolver. The policies returned by the resolver are
+    /// added to the engine and their imports are resolved in turn. Circular imports among the
+    /// policies being loaded are reported as errors. Imports that the resolver does not know
+    /// about are left as is.
+    ///
+    /// ```
+    /// # use regorus::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut engine = Engine::new();
+    /// engine.set_module_resolver(Some(Box::new(|package: &str| {
+    ///     Ok(match package {
+    ///         "data.lib" => Some((
+    ///             "lib.rego".to_string(),
+    ///             "package lib\n admins := {\"alice\"}".to_string(),
+    ///         )),
+    ///         _ => None,
+    ///     })
+    /// })));
+    ///
+    /// engine.add_policy(
+    ///    "test.rego".to_string(),
+    ///    r#"
+    ///    package test
+    ///    import rego.v1
+    ///    import data.lib
+    ///
+    ///    allow if input.user in lib.admins
+    ///    "#.to_string())?;
+    ///
+    /// assert_eq!(engine.get_packages()?, ["data.lib", "data.test"]);
+    ///
+    /// engine.set_input(Value::from_json_str(r#"{"user": "alice"}"#)?);
+    /// assert_eq!(engine.eval_rule("data.test.allow".to_string())?, Value::from(true));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_module_resolver(&mut self, resolver: Option<Box<dyn ModuleResolver>>) {
+        self.module_resolver = resolver.map(Rc::from);
+    }
+
+    /// Send output from print statements to the given [`PrintHook`] instead of stderr.
+    ///
+    /// Each message is prefixed with the location of the print statement. Gathering prints
+    /// via [`Engine::set_gather_prints`] takes precedence over the hook. Pass `None` to
+    /// print to stderr again.
+    ///
+    /// Like OPA, nothing is printed if any of the arguments is undefined.
+    ///
+    /// ```rust
+    /// # use regorus::*;
+    /// # use anyhow::{bail, Result};
+    /// # use std::sync::{Arc, Mutex};
+    /// # fn main() -> Result<()> {
+    /// let mut engine = Engine::new();
+    /// engine.add_policy(
+    ///    "test.rego".to_string(),
+    ///    "package test\n x { print(\"x is\", input.x) }".to_string())?;
+    ///
+    /// let output = Arc::new(Mutex::new(vec![]));
+    /// let sink = output.clone();
+    /// engine.set_print_hook(Some(Box::new(move |msg: &str| {
+    ///     sink.lock().unwrap().push(msg.to_string());
+    /// })));
+    ///
+    /// engine.set_input(Value::from_json_str(r#"{"x": [1, "a"]}"#)?);
+    /// engine.eval_rule("data.test.x".to_string())?;
+    /// assert_eq!(*output.lock().unwrap(), vec!["test.rego:2: x is [1, \"a\"]"]);
+    ///
+    /// // input.x is undefined, so nothing is printed.
+    /// engine.set_input(Value::new_object());
+    /// engine.eval_rule("data.test.x".to_string())?;
+    /// assert_eq!(output.lock().unwrap().len(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_print_hook(&mut self, hook: Option<Box<dyn PrintHook>>) {
+        self.interpreter.set_print_hook(hook);
+    }
+
     /// Take the gathered output of print statements.
     ///
     /// ```rust
@@ -860,6 +1608,59 @@ impl Engine {
         self.interpreter.take_prints()
     }
 
+    /// Record [`TraceEvent`]s explaining subsequent evaluations.
+    ///
+    /// When enabled, the engine records entry into rules and functions, evaluation of
+    /// statements, binding of variables and `trace` calls. Disabling discards any events
+    /// that have not been taken. See [`Engine::take_explanation`].
+    pub fn set_enable_explanation(&mut self, enable: bool) {
+        self.interpreter.set_enable_explanation(enable);
+    }
+
+    /// Take the [`TraceEvent`]s recorded since explanations were enabled or last taken.
+    ///
+    /// ```rust
+    /// # use regorus::*;
+    /// # use anyhow::{bail, Result};
+    /// # fn main() -> Result<()> {
+    /// let mut engine = Engine::new();
+    /// engine.add_policy(
+    ///    "test.rego".to_string(),
+    ///    r#"package test
+    ///       import rego.v1
+    ///
+    ///       deny if {
+    ///         input.user != "admin"
+    ///         trace(sprintf("user %s is not an admin", [input.user]))
+    ///       }
+    ///    "#.to_string())?;
+    ///
+    /// // Explanation must be enabled first.
+    /// assert!(engine.take_explanation().is_err());
+    ///
+    /// engine.set_enable_explanation(true);
+    /// engine.set_input(Value::from_json_str(r#"{"user": "bob"}"#)?);
+    /// engine.eval_rule("data.test.deny".to_string())?;
+    ///
+    /// let events = engine.take_explanation()?;
+    /// assert!(events.iter().any(|e| e.op == TraceOp::Enter && e.message == "data.test.deny"));
+    ///
+    /// // Each event carries the location it pertains to.
+    /// let Some(note) = events.iter().find(|e| e.op == TraceOp::Note) else {
+    ///   bail!("trace call not recorded");
+    /// };
+    /// assert_eq!(note.message, "user bob is not an admin");
+    /// assert_eq!((note.file.as_str(), note.location.row), ("test.rego", 6));
+    ///
+    /// // Events are taken only once.
+    /// assert!(engine.take_explanation()?.is_empty());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn take_explanation(&mut self) -> Result<Vec<TraceEvent>> {
+        self.interpreter.take_explanation()
+    }
+
     /// Get the policies and corresponding AST.
     ///
     ///
@@ -9,7 +9,7 @@ use crate::scheduler::*;
 use crate::utils::*;
 use crate::value::*;
 use crate::*;
-use crate::{Expression, Extension, Location, QueryResult, QueryResults};
+use crate::{Expression, Extension, Location, QueryResult, QueryResults, TraceEvent, TraceOp};
 
 use alloc::collections::btree_map::Entry as BTreeMapEntry;
 use alloc::collections::{BTreeMap, BTreeSet};
@@ -36,6 +36,27 @@ enum FunctionModifier {
     Value(Value),
 }
 
+// State used during partial evaluation.
+#[derive(Debug, Default)]
+struct PartialState {
+    // Paths of the unknown references.
+    unknowns: Vec<Vec<String>>,
+    // Residuals that variables have been bound to, per scope.
+    aliases: Vec<BTreeMap<String, String>>,
+    // Whether a rule (or function) depends on the unknowns.
+    rules: BTreeMap<String, bool>,
+}
+
+impl PartialState {
+    fn alias(&self, name: &str) -> Option<&String> {
+        self.aliases.last().and_then(|a| a.get(name))
+    }
+
+    fn is_alias(&self, name: &str) -> bool {
+        self.alias(name).is_some()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Interpreter {
     modules: Vec<Ref<Module>>,
@@ -59,6 +80,16 @@ pub struct Interpreter {
     rule_values: BTreeMap<Vec<Value>, (Value, Ref<Expr>)>,
     active_rules: Vec<Ref<Rule>>,
     builtins_cache: BTreeMap<(&'static str, Vec<Value>), Value>,
+    #[cfg(feature = "time")]
+    clock: Option<i64>,
+    #[cfg(feature = "net")]
+    resolver: Option<Rc<dyn Resolver>>,
+    #[cfg(feature = "std")]
+    rand_seed: Option<u64>,
+    #[cfg(feature = "opa-runtime")]
+    runtime: Option<Value>,
+    #[cfg(feature = "std")]
+    rng: Option<rand::rngs::StdRng>,
     no_rules_lookup: bool,
     traces: Option<Vec<Rc<str>>>,
     #[cfg(feature = "deprecated")]
@@ -74,7 +105,12 @@ pub struct Interpreter {
 
     gather_prints: bool,
     prints: Vec<String>,
+    print_hook: Option<Rc<dyn PrintHook>>,
+    explanation: Option<Vec<TraceEvent>>,
     rule_paths: Set<String>,
+    max_steps: Option<u64>,
+    steps: u64,
+    cancellation_flag: Option<alloc::sync::Arc<core::sync::atomic::AtomicBool>>,
 }
 
 impl Default for Interpreter {
@@ -185,6 +221,16 @@ impl Interpreter {
             rule_values: BTreeMap::new(),
             active_rules: vec![],
             builtins_cache: BTreeMap::new(),
+            #[cfg(feature = "time")]
+            clock: None,
+            #[cfg(feature = "net")]
+            resolver: None,
+            #[cfg(feature = "std")]
+            rand_seed: None,
+            #[cfg(feature = "opa-runtime")]
+            runtime: None,
+            #[cfg(feature = "std")]
+            rng: None,
             no_rules_lookup: false,
             traces: None,
             #[cfg(feature = "deprecated")]
@@ -200,7 +246,12 @@ impl Interpreter {
 
             gather_prints: false,
             prints: Vec::default(),
+            print_hook: None,
+            explanation: None,
             rule_paths: Set::new(),
+            max_steps: None,
+            steps: 0,
+            cancellation_flag: None,
         }
     }
 
@@ -266,6 +317,53 @@ impl Interpreter {
         self.scopes = vec![Scope::new()];
         self.contexts = vec![];
         self.rule_values.clear();
+        self.steps = 0;
+
+        // Values of builtins like time.now_ns are cached only for the duration of an evaluation.
+        self.builtins_cache.clear();
+        #[cfg(feature = "time")]
+        if let Some(now_ns) = self.clock {
+            self.builtins_cache
+                .insert(("time.now_ns", vec![]), Value::from(now_ns));
+        }
+
+        // Restart the random number sequence so that evaluations are reproducible.
+        #[cfg(feature = "std")]
+        {
+            use rand::SeedableRng;
+            self.rng = self.rand_seed.map(rand::rngs::StdRng::seed_from_u64);
+        }
+    }
+
+    pub fn set_max_steps(&mut self, max_steps: Option<u64>) {
+        self.max_steps = max_steps;
+    }
+
+    pub fn set_cancellation_flag(
+        &mut self,
+        flag: Option<alloc::sync::Arc<core::sync::atomic::AtomicBool>>,
+    ) {
+        self.cancellation_flag = flag;
+    }
+
+    #[cfg(feature = "time")]
+    pub fn set_clock(&mut self, now_ns: Option<i64>) {
+        self.clock = now_ns;
+    }
+
+    #[cfg(feature = "std")]
+    pub fn set_rand_seed(&mut self, seed: Option<u64>) {
+        self.rand_seed = seed;
+    }
+
+    #[cfg(feature = "opa-runtime")]
+    pub fn set_runtime(&mut self, runtime: Option<Value>) {
+        self.runtime = runtime;
+    }
+
+    #[cfg(feature = "net")]
+    pub fn set_resolver(&mut self, resolver: Option<Box<dyn Resolver>>) {
+        self.resolver = resolver.map(Rc::from);
     }
 
     fn current_module(&self) -> Result<Ref<Module>> {
@@ -290,12 +388,43 @@ impl Interpreter {
     fn add_variable(&mut self, name: &SourceStr, value: Value) -> Result<()> {
         // Only add the variable if the key is not "_"
         if name.text() != "_" {
+            if value != Value::Undefined {
+                self.explain_bind(name, &value);
+            }
             self.current_scope_mut()?.insert(name.clone(), value);
         }
 
         Ok(())
     }
 
+    #[inline(always)]
+    fn explain(&mut self, op: TraceOp, span: &Span, message: impl FnOnce() -> String) {
+        if let Some(events) = &mut self.explanation {
+            events.push(TraceEvent {
+                op,
+                file: span.source.file().clone(),
+                location: Location {
+                    row: span.line,
+                    col: span.col,
+                },
+                message: message(),
+            });
+        }
+    }
+
+    #[inline(always)]
+    fn explain_bind(&mut self, name: &SourceStr, value: &Value) {
+        if let Some(events) = &mut self.explanation {
+            let (row, col) = name.line_col();
+            events.push(TraceEvent {
+                op: TraceOp::Bind,
+                file: name.source().file().clone(),
+                location: Location { row, col },
+                message: format!("{name} = {value}"),
+            });
+        }
+    }
+
     fn add_variable_or(&mut self, name: &SourceStr) -> Result<Value> {
         for scope in self.scopes.iter().rev() {
             if let Some(variable) = scope.get(name) {
@@ -311,6 +440,9 @@ impl Interpreter {
     fn variables_assignment(&mut self, name: &SourceStr, value: &Value) -> Result<()> {
         if let Some(variable) = self.current_scope_mut()?.get_mut(name) {
             *variable = value.clone();
+            if value != &Value::Undefined {
+                self.explain_bind(name, value);
+            }
             Ok(())
         } else if name.text() == "_" {
             Ok(())
@@ -1331,6 +1463,7 @@ impl Interpreter {
     fn eval_stmt(&mut self, stmt: &LiteralStmt, stmts: &[&LiteralStmt]) -> Result<bool> {
         let (saved_state, skip_exec) = self.apply_with_modifiers(stmt)?;
         let r = if !skip_exec {
+            self.explain(TraceOp::Eval, &stmt.span, || stmt.span.text().to_string());
             self.eval_stmt_impl(stmt, stmts)
         } else {
             Ok(false)
@@ -2117,13 +2250,25 @@ impl Interpreter {
     }
 
     fn lookup_function_by_name(&self, path: &str) -> Option<(&Vec<Ref<Rule>>, &Ref<Module>)> {
-        let mut path = path.to_owned();
+        let mut full_path = path.to_owned();
         if !path.starts_with("data.") {
-            path = self.current_module_path.clone() + "." + &path;
+            full_path = self.current_module_path.clone() + "." + path;
         }
 
-        match self.functions.get(&path) {
+        match self.functions.get(&full_path) {
             Some((f, _, m)) => Some((f, m)),
+            _ if !path.starts_with("data.") => {
+                // The function may be referred to via an import.
+                let (head, rest) = path.split_once('.').unwrap_or((path, ""));
+                let import = self
+                    .imports
+                    .get(&(self.current_module_path.clone() + "." + head))?;
+                let mut full_path = get_path_string(import, None).ok()?;
+                if !rest.is_empty() {
+                    full_path = full_path + "." + rest;
+                }
+                self.functions.get(&full_path).map(|(f, _, m)| (f, m))
+            }
             _ => None,
         }
     }
@@ -2148,7 +2293,28 @@ impl Interpreter {
             }
         }
 
-        let v = match builtin.0(span, params, &args[..], self.strict_builtin_errors) {
+        // Some builtins need state configured on the engine.
+        let result = match name {
+            #[cfg(feature = "net")]
+            "net.lookup_ip_addr" => {
+                builtins::net::lookup_ip_addr(self.resolver.as_deref(), span, params, &args[..])
+            }
+            #[cfg(feature = "std")]
+            "rand.intn" => match &mut self.rng {
+                Some(rng) => builtins::numbers::intn_with_rng(rng, span, params, &args[..]),
+                None => builtin.0(span, params, &args[..], self.strict_builtin_errors),
+            },
+            #[cfg(feature = "opa-runtime")]
+            "opa.runtime" => builtins::opa::runtime(self.runtime.as_ref(), span, params, &args[..]),
+            #[cfg(all(feature = "std", feature = "uuid"))]
+            "uuid.rfc4122" => match &mut self.rng {
+                Some(rng) => builtins::uuid::rfc4122_with_rng(rng, span, params, &args[..]),
+                None => builtin.0(span, params, &args[..], self.strict_builtin_errors),
+            },
+            _ => builtin.0(span, params, &args[..], self.strict_builtin_errors),
+        };
+
+        let v = match result {
             Ok(v) => v,
             // Ignore errors if we are not evaluating in strict mode.
             Err(_) if !self.strict_builtin_errors => return Ok(Value::Undefined),
@@ -2158,9 +2324,11 @@ impl Interpreter {
         // Handle trace function.
         // TODO: with modifier.
         if name == "trace" {
-            if let (Some(traces), Value::String(msg)) = (&mut self.traces, &v) {
-                traces.push(msg.clone());
-                return Ok(Value::Bool(true));
+            if let Value::String(msg) = &args[0] {
+                if let Some(traces) = &mut self.traces {
+                    traces.push(msg.clone());
+                }
+                self.explain(TraceOp::Note, span, || msg.to_string());
             }
         }
 
@@ -2236,10 +2404,10 @@ impl Interpreter {
             bail!(span.error(&format!("print supports upto {MAX_ARGS} arguments")));
         }
 
-        // If not compiling for std target, return early if gathering is not
-        // requested.
+        // If not compiling for std target, return early if there is nowhere to
+        // send the output.
         #[cfg(not(feature = "std"))]
-        if !self.gather_prints {
+        if !self.gather_prints && self.print_hook.is_none() {
             return Ok(Value::Bool(true));
         }
 
@@ -2249,28 +2417,116 @@ impl Interpreter {
                 msg.push(' ');
             }
             match self.eval_expr(p)? {
-                Value::Undefined => msg.push_str("<undefined>"),
+                // Like OPA, an undefined argument makes the whole print a no-op.
+                Value::Undefined => return Ok(Value::Bool(true)),
                 // Do not print quotes for string values.
                 Value::String(s) => msg.push_str(&format!("{s}")),
                 a => Self::to_printable(&a, &mut msg),
             }
         }
 
+        // Prefix location information when gathering or sending to the hook.
         if self.gather_prints {
-            // Prefix location information.
             self.prints
                 .push(format!("{}:{}: {msg}", span.source.file(), span.line));
-        }
-
-        // Print to stderr only if not gathering.
-        #[cfg(feature = "std")]
-        if !self.gather_prints {
+        } else if let Some(hook) = &self.print_hook {
+            hook.print(&format!("{}:{}: {msg}", span.source.file(), span.line));
+        } else {
+            // Print to stderr only if neither gathering nor hooked.
+            #[cfg(feature = "std")]
             std::eprintln!("{msg}");
         }
 
         Ok(Value::Bool(true))
     }
 
+    fn eval_rego_metadata(&self, span: &Span, name: &str, params: &[ExprRef]) -> Result<Value> {
+        if !params.is_empty() {
+            bail!(span.error(&format!("`{name}` expects 0 arguments")));
+        }
+
+        // Outside of rules (e.g. in queries) there is no metadata.
+        let Some(rule) = self.active_rules.last() else {
+            return Ok(match name {
+                "rego.metadata.rule" => Value::new_object(),
+                _ => Value::new_array(),
+            });
+        };
+        let Some(module) = self.modules.iter().find(|m| m.policy.contains(rule)) else {
+            bail!("internal error: module of active rule not found");
+        };
+
+        let package_path = Self::get_path_string(&module.package.refr, Some("data"))?;
+        let rule_path = Self::get_path_string(Self::get_rule_refr(rule), Some(&package_path))?;
+
+        let rule_scoped = match rule.annotation() {
+            Some(a) if a.scope == "rule" => Some(a.value.clone()),
+            _ => None,
+        };
+
+        if name == "rego.metadata.rule" {
+            return Ok(rule_scoped.unwrap_or_else(Value::new_object));
+        }
+
+        let link = |path: &str, annotations: Option<Value>| {
+            let mut obj = BTreeMap::new();
+            obj.insert(
+                Value::from("path"),
+                Value::from_array(path.split('.').map(Value::from).collect()),
+            );
+            if let Some(a) = annotations {
+                obj.insert(Value::from("annotations"), a);
+            }
+            Value::from_map(obj)
+        };
+
+        // The active rule is always the first link, followed by document, package and
+        // subpackages scoped annotations, ending at the root.
+        let mut chain = vec![link(&rule_path, rule_scoped)];
+
+        'document: for m in &self.modules {
+            let m_path = Self::get_path_string(&m.package.refr, Some("data"))?;
+            for r in &m.policy {
+                if let Some(a) = r.annotation() {
+                    if a.scope == "document"
+                        && Self::get_path_string(Self::get_rule_refr(r), Some(&m_path))?
+                            == rule_path
+                    {
+                        chain.push(link(&rule_path, Some(a.value.clone())));
+                        break 'document;
+                    }
+                }
+            }
+        }
+
+        let package_annotation = |path: &str, scope: &str| -> Result<Option<Value>> {
+            for m in &self.modules {
+                if let Some(a) = &m.package.annotation {
+                    if a.scope == scope
+                        && Self::get_path_string(&m.package.refr, Some("data"))? == path
+                    {
+                        return Ok(Some(a.value.clone()));
+                    }
+                }
+            }
+            Ok(None)
+        };
+
+        if let Some(a) = package_annotation(&package_path, "package")? {
+            chain.push(link(&package_path, Some(a)));
+        }
+
+        let mut path = package_path.as_str();
+        while let Some(idx) = path.rfind('.') {
+            if let Some(a) = package_annotation(path, "subpackages")? {
+                chain.push(link(path, Some(a)));
+            }
+            path = &path[..idx];
+        }
+
+        Ok(Value::from_array(chain))
+    }
+
     fn eval_call_impl(
         &mut self,
         span: &Span,
@@ -2331,6 +2587,8 @@ impl Interpreter {
                     (&empty, None)
                 } else if fcn_path == "print" {
                     return self.eval_print(span, params, param_values);
+                } else if fcn_path == "rego.metadata.rule" || fcn_path == "rego.metadata.chain" {
+                    return self.eval_rego_metadata(span, &fcn_path, params);
                 }
                 // Look up builtin function.
                 else if let Some(builtin) = self.lookup_builtin(span, &fcn_path)? {
@@ -2404,6 +2662,17 @@ impl Interpreter {
             // the local variables of callee function.
             let scopes = core::mem::take(&mut self.scopes);
 
+            if self.explanation.is_some() {
+                let path = match &fcn_module {
+                    Some(m) => Self::get_path_string(
+                        Self::get_rule_refr(&fcn_rule),
+                        Some(&Self::get_path_string(&m.package.refr, Some("data"))?),
+                    )?,
+                    None => fcn_path.clone(),
+                };
+                self.explain(TraceOp::Enter, fcn_rule.span(), || path);
+            }
+
             // Set the arguments scope.
             let args_scope = Scope::new();
             self.scopes.push(args_scope);
@@ -2436,6 +2705,8 @@ impl Interpreter {
             let prev_module = self.set_current_module(fcn_module.clone())?;
             let value = match self.eval_rule_bodies(ctx, span, bodies) {
                 Ok(v) => v,
+                // Exceeding the budget or cancellation aborts the evaluation.
+                Err(e) if e.is::<BudgetExceeded>() || e.is::<Cancelled>() => return Err(e),
                 Err(e) => {
                     // If the rule produces an error, save the error.
                     errors.push(e);
@@ -2498,6 +2769,9 @@ impl Interpreter {
                         if let Rule::Default { value, .. } = rule.as_ref() {
                             match self.eval_expr(value) {
                                 Ok(v) => results.push(v),
+                                Err(e) if e.is::<BudgetExceeded>() || e.is::<Cancelled>() => {
+                                    return Err(e)
+                                }
                                 Err(e) => errors.push(e),
                             }
                         }
@@ -2787,6 +3061,26 @@ impl Interpreter {
     }
 
     fn eval_expr(&mut self, expr: &ExprRef) -> Result<Value> {
+        if let Some(flag) = &self.cancellation_flag {
+            if flag.load(core::sync::atomic::Ordering::Relaxed) {
+                return Err(anyhow::Error::msg(Cancelled));
+            }
+        }
+
+        if let Some(max_steps) = self.max_steps {
+            self.steps += 1;
+            if self.steps > max_steps {
+                let span = expr.span();
+                return Err(anyhow::Error::msg(BudgetExceeded {
+                    max_steps,
+                    file: span.source.get_path().clone(),
+                    line: span.line,
+                    col: span.col,
+                    message: span.message("error", "evaluation budget exceeded"),
+                }));
+            }
+        }
+
         #[cfg(feature = "coverage")]
         if self.enable_coverage {
             let span = expr.span();
@@ -3293,6 +3587,7 @@ impl Interpreter {
                 span,
                 head: rule_head,
                 bodies: rule_body,
+                ..
             } => {
                 match rule_head {
                     RuleHead::Compr { refr, .. } | RuleHead::Set { refr, .. } => {
@@ -3409,6 +3704,12 @@ impl Interpreter {
         let scopes = core::mem::take(&mut self.scopes);
         let prev_module = self.set_current_module(Some(module.clone()))?;
 
+        if self.explanation.is_some() {
+            let refr = Self::get_rule_refr(rule);
+            let path = Self::get_path_string(refr, Some(&self.current_module_path))?;
+            self.explain(TraceOp::Enter, rule.span(), || path);
+        }
+
         let res = self.eval_rule_impl(module, rule);
 
         self.set_current_module(prev_module)?;
@@ -3502,6 +3803,763 @@ impl Interpreter {
         }
     }
 
+    pub fn partial_eval_query(
+        &mut self,
+        module: &Ref<Module>,
+        query: &Ref<Query>,
+        unknowns: &[String],
+    ) -> Result<Vec<Vec<String>>> {
+        let mut state = PartialState::default();
+        for u in unknowns {
+            let comps: Vec<String> = u.split('.').map(|c| c.to_string()).collect();
+            if !matches!(comps[0].as_str(), "input" | "data") || comps.iter().any(|c| c.is_empty())
+            {
+                bail!("unknown `{u}` must be a reference into input or data");
+            }
+            state.unknowns.push(comps);
+        }
+
+        let prev_module = self.set_current_module(Some(module.clone()))?;
+        let scopes = core::mem::take(&mut self.scopes);
+        self.scopes.push(Scope::new());
+        state.aliases.push(BTreeMap::new());
+
+        let r = self.partial_eval_stmts(&query.stmts, &mut state);
+
+        self.scopes = scopes;
+        self.set_current_module(prev_module)?;
+        r
+    }
+
+    fn partial_unsupported(span: &Span, what: &str) -> anyhow::Error {
+        span.error(&format!(
+            "partial evaluation of {what} involving unknowns is not supported"
+        ))
+    }
+
+    fn partial_eval_stmts(
+        &mut self,
+        stmts: &[LiteralStmt],
+        state: &mut PartialState,
+    ) -> Result<Vec<Vec<String>>> {
+        // Disjunction of conjunctions. Initially the empty conjunction i.e true.
+        let mut result = vec![vec![]];
+        for stmt in stmts {
+            if result.is_empty() {
+                break;
+            }
+            let alternatives = self.partial_eval_stmt(stmt, state)?;
+            let mut product = vec![];
+            for conj in &result {
+                for alt in &alternatives {
+                    let mut c = conj.clone();
+                    c.extend(alt.iter().cloned());
+                    product.push(c);
+                }
+            }
+            result = product;
+        }
+        Ok(result)
+    }
+
+    fn partial_eval_stmt(
+        &mut self,
+        stmt: &LiteralStmt,
+        state: &mut PartialState,
+    ) -> Result<Vec<Vec<String>>> {
+        if !stmt.with_mods.is_empty() {
+            bail!(stmt
+                .span
+                .error("partial evaluation of with modifiers is not supported"));
+        }
+
+        match &stmt.literal {
+            Literal::SomeVars { vars, .. } => {
+                for var in vars {
+                    self.add_variable(&var.source_str(), Value::Undefined)?;
+                }
+                Ok(vec![vec![]])
+            }
+            Literal::SomeIn { span, .. } => Err(Self::partial_unsupported(span, "some-in")),
+            Literal::Every { span, .. } => Err(Self::partial_unsupported(span, "every")),
+            Literal::Expr { expr, .. } => {
+                if let Expr::AssignExpr { op, lhs, rhs, .. } = expr.as_ref() {
+                    if let Expr::Var((span, _)) = lhs.as_ref() {
+                        let name = span.source_str();
+                        let unbound =
+                            matches!(self.lookup_local_var(&name), None | Some(Value::Undefined))
+                                && !state.is_alias(name.text());
+                        if *op == AssignOp::ColEq || (unbound && !self.partial_is_rule(lhs)) {
+                            return self.partial_eval_assign(&name, rhs, state);
+                        }
+                    }
+                }
+
+                if !self.partial_depends(expr, state)? {
+                    return Ok(match self.eval_expr(expr)? {
+                        Value::Undefined | Value::Bool(false) => vec![],
+                        _ => vec![vec![]],
+                    });
+                }
+
+                if let Some(path) = self.partial_rule_path(expr)? {
+                    return self.partial_inline_rule(expr.span(), &path, state);
+                }
+
+                Ok(match self.partial_residual(expr, state)? {
+                    Some(r) => vec![vec![r]],
+                    None => vec![],
+                })
+            }
+            Literal::NotExpr { span, expr } => {
+                if !self.partial_depends(expr, state)? {
+                    return Ok(match self.eval_expr(expr)? {
+                        Value::Undefined | Value::Bool(false) => vec![vec![]],
+                        _ => vec![],
+                    });
+                }
+
+                let alternatives = match self.partial_rule_path(expr)? {
+                    Some(path) => self.partial_inline_rule(expr.span(), &path, state)?,
+                    None => match self.partial_residual(expr, state)? {
+                        Some(r) => vec![vec![r]],
+                        None => vec![],
+                    },
+                };
+
+                match alternatives.as_slice() {
+                    [] => Ok(vec![vec![]]),
+                    [conj] if conj.is_empty() => Ok(vec![]),
+                    [conj] if conj.len() == 1 => Ok(vec![vec![format!("not {}", conj[0])]]),
+                    _ => Err(Self::partial_unsupported(span, "negated rule")),
+                }
+            }
+        }
+    }
+
+    fn partial_eval_assign(
+        &mut self,
+        name: &SourceStr,
+        rhs: &ExprRef,
+        state: &mut PartialState,
+    ) -> Result<Vec<Vec<String>>> {
+        if !self.partial_depends(rhs, state)? {
+            return Ok(match self.eval_expr(rhs)? {
+                Value::Undefined => vec![],
+                value => {
+                    self.add_variable(name, value)?;
+                    vec![vec![]]
+                }
+            });
+        }
+
+        // Record the residual so that later uses of the variable are replaced by it.
+        match self.partial_residual(rhs, state)? {
+            Some(r) => {
+                if let Some(aliases) = state.aliases.last_mut() {
+                    aliases.insert(name.text().to_string(), r);
+                }
+                Ok(vec![vec![]])
+            }
+            None => Ok(vec![]),
+        }
+    }
+
+    fn partial_inline_rule(
+        &mut self,
+        span: &Span,
+        path: &str,
+        state: &mut PartialState,
+    ) -> Result<Vec<Vec<String>>> {
+        if let Some(defaults) = self.default_rules.get(path) {
+            for (rule, _) in defaults.clone() {
+                if let Rule::Default { value, .. } = rule.as_ref() {
+                    if !matches!(value.as_ref(), Expr::False(_)) {
+                        return Err(Self::partial_unsupported(span, "rule with default value"));
+                    }
+                }
+            }
+        }
+
+        let rules = self.rules.get(path).cloned().unwrap_or_default();
+        let mut result = vec![];
+        for rule in rules {
+            let (head_assign, bodies) = match rule.as_ref() {
+                Rule::Spec {
+                    head: RuleHead::Compr { assign, .. },
+                    bodies,
+                    ..
+                } if bodies.len() <= 1 => (assign, bodies),
+                _ => return Err(Self::partial_unsupported(span, &format!("rule `{path}`"))),
+            };
+
+            let r = self.partial_in_rule_scope(&rule, state, |this, state| {
+                let (mut alternatives, assign) = match bodies.first() {
+                    Some(body) => (
+                        this.partial_eval_stmts(&body.query.stmts, state)?,
+                        body.assign.as_ref().or(head_assign.as_ref()),
+                    ),
+                    None => (vec![vec![]], head_assign.as_ref()),
+                };
+
+                if let Some(assign) = assign {
+                    if alternatives.is_empty() {
+                        // Body never holds.
+                    } else if !this.partial_depends(&assign.value, state)? {
+                        if matches!(
+                            this.eval_expr(&assign.value)?,
+                            Value::Undefined | Value::Bool(false)
+                        ) {
+                            alternatives.clear();
+                        }
+                    } else {
+                        match this.partial_residual(&assign.value, state)? {
+                            Some(r) => alternatives.iter_mut().for_each(|c| c.push(r.clone())),
+                            None => alternatives.clear(),
+                        }
+                    }
+                }
+                Ok(alternatives)
+            })?;
+            result.extend(r);
+        }
+
+        Ok(result)
+    }
+
+    // Resolve a reference to the path of the rule it refers to.
+    fn partial_rule_prefix(&self, refr: &ExprRef) -> Result<Option<(String, bool)>> {
+        let comps = Self::get_rule_path_components(refr)?;
+        let root = match Self::partial_root_var(refr) {
+            Some(span) if !comps.is_empty() => span,
+            _ => return Ok(None),
+        };
+        if root.text() == "input" || self.lookup_local_var(&root.source_str()).is_some() {
+            return Ok(None);
+        }
+
+        let mut path = if root.text() == "data" {
+            vec!["data".to_string()]
+        } else {
+            vec![self.current_module_path.clone()]
+        };
+        let skip = if root.text() == "data" { 1 } else { 0 };
+        let comps: Vec<String> = comps[skip..].iter().map(|c| c.to_string()).collect();
+        for (idx, c) in comps.iter().enumerate() {
+            path.push(c.clone());
+            let p = path.join(".");
+            if self.rules.contains_key(&p) || self.default_rules.contains_key(&p) {
+                return Ok(Some((p, idx + 1 == comps.len())));
+            }
+        }
+
+        // The reference may be a prefix of rule paths.
+        let p = path.join(".") + ".";
+        let is_prefix = self.rules.keys().any(|k| k.starts_with(&p));
+        Ok(is_prefix.then(|| (path.join("."), false)))
+    }
+
+    fn partial_rule_path(&self, expr: &ExprRef) -> Result<Option<String>> {
+        if !matches!(
+            expr.as_ref(),
+            Expr::Var(_) | Expr::RefDot { .. } | Expr::RefBrack { .. }
+        ) {
+            return Ok(None);
+        }
+        Ok(match self.partial_rule_prefix(expr)? {
+            Some((path, true)) if self.rules.contains_key(&path) => Some(path),
+            _ => None,
+        })
+    }
+
+    fn partial_is_rule(&self, expr: &ExprRef) -> bool {
+        matches!(self.partial_rule_prefix(expr), Ok(Some(_)))
+    }
+
+    fn partial_root_var(mut expr: &ExprRef) -> Option<&Span> {
+        loop {
+            expr = match expr.as_ref() {
+                Expr::RefDot { refr, .. } | Expr::RefBrack { refr, .. } => refr,
+                Expr::Var((span, _)) => return Some(span),
+                _ => return None,
+            }
+        }
+    }
+
+    fn partial_rule_depends(&mut self, path: &str, state: &mut PartialState) -> Result<bool> {
+        if let Some(d) = state.rules.get(path) {
+            return Ok(*d);
+        }
+        // Guard against cycles. Recursion is rejected elsewhere.
+        state.rules.insert(path.to_string(), false);
+
+        let prefix = path.to_string() + ".";
+        let mut rules: Vec<Ref<Rule>> = vec![];
+        for (k, v) in self.rules.iter() {
+            if k == path || k.starts_with(&prefix) {
+                rules.extend(v.iter().cloned());
+            }
+        }
+        for (k, v) in self.default_rules.iter() {
+            if k == path || k.starts_with(&prefix) {
+                rules.extend(v.iter().map(|(r, _)| r.clone()));
+            }
+        }
+
+        let mut depends = false;
+        for rule in rules {
+            if self.partial_rule_body_depends(&rule, state)? {
+                depends = true;
+                break;
+            }
+        }
+        state.rules.insert(path.to_string(), depends);
+        Ok(depends)
+    }
+
+    fn partial_rule_body_depends(
+        &mut self,
+        rule: &Ref<Rule>,
+        state: &mut PartialState,
+    ) -> Result<bool> {
+        self.partial_in_rule_scope(rule, state, |this, state| {
+            let mut exprs = vec![];
+            let mut queries = vec![];
+            match rule.as_ref() {
+                Rule::Spec { head, bodies, .. } => {
+                    match head {
+                        RuleHead::Compr { assign, .. } => {
+                            exprs.extend(assign.iter().map(|a| a.value.clone()))
+                        }
+                        RuleHead::Set { key, .. } => exprs.extend(key.iter().cloned()),
+                        RuleHead::Func { assign, .. } => {
+                            exprs.extend(assign.iter().map(|a| a.value.clone()))
+                        }
+                    }
+                    for body in bodies {
+                        exprs.extend(body.assign.iter().map(|a| a.value.clone()));
+                        queries.push(body.query.clone());
+                    }
+                }
+                Rule::Default { value, .. } => exprs.push(value.clone()),
+            }
+            for e in &exprs {
+                if this.partial_depends(e, state)? {
+                    return Ok(true);
+                }
+            }
+            for q in &queries {
+                if this.partial_query_depends(q, state)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        })
+    }
+
+    // Run f in the module of the given rule with fresh scopes.
+    fn partial_in_rule_scope<T>(
+        &mut self,
+        rule: &Ref<Rule>,
+        state: &mut PartialState,
+        f: impl FnOnce(&mut Self, &mut PartialState) -> Result<T>,
+    ) -> Result<T> {
+        let module = self.get_rule_module(rule)?;
+        let prev_module = self.set_current_module(Some(module))?;
+        let scopes = core::mem::take(&mut self.scopes);
+        self.scopes.push(Scope::new());
+        state.aliases.push(BTreeMap::new());
+
+        let r = f(self, state);
+
+        state.aliases.pop();
+        self.scopes = scopes;
+        self.set_current_module(prev_module)?;
+        r
+    }
+
+    fn partial_query_depends(
+        &mut self,
+        query: &Ref<Query>,
+        state: &mut PartialState,
+    ) -> Result<bool> {
+        for stmt in &query.stmts {
+            for m in &stmt.with_mods {
+                if self.partial_depends(&m.r#as, state)? {
+                    return Ok(true);
+                }
+            }
+            let depends = match &stmt.literal {
+                Literal::SomeVars { .. } => false,
+                Literal::SomeIn {
+                    key,
+                    value,
+                    collection,
+                    ..
+                } => {
+                    self.partial_depends(value, state)?
+                        || self.partial_depends(collection, state)?
+                        || match key {
+                            Some(k) => self.partial_depends(k, state)?,
+                            None => false,
+                        }
+                }
+                Literal::Expr { expr, .. } | Literal::NotExpr { expr, .. } => {
+                    self.partial_depends(expr, state)?
+                }
+                Literal::Every { domain, query, .. } => {
+                    self.partial_depends(domain, state)?
+                        || self.partial_query_depends(query, state)?
+                }
+            };
+            if depends {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    // Determine whether the value of an expression depends on the unknowns.
+    fn partial_depends(&mut self, expr: &ExprRef, state: &mut PartialState) -> Result<bool> {
+        use Expr::*;
+        Ok(match expr.as_ref() {
+            String(_) | RawString(_) | Number(_) | True(_) | False(_) | Null(_) => false,
+            Var(_) | RefDot { .. } | RefBrack { .. } => {
+                let mut e = expr;
+                loop {
+                    e = match e.as_ref() {
+                        RefDot { refr, .. } => refr,
+                        RefBrack { refr, index, .. } => {
+                            if self.partial_depends(index, state)? {
+                                return Ok(true);
+                            }
+                            refr
+                        }
+                        Var(_) => break,
+                        _ => return self.partial_depends(e, state),
+                    }
+                }
+                self.partial_ref_depends(expr, state)?
+            }
+            Array { items, .. } | Set { items, .. } => {
+                for item in items {
+                    if self.partial_depends(item, state)? {
+                        return Ok(true);
+                    }
+                }
+                false
+            }
+            Object { fields, .. } => {
+                for (_, k, v) in fields {
+                    if self.partial_depends(k, state)? || self.partial_depends(v, state)? {
+                        return Ok(true);
+                    }
+                }
+                false
+            }
+            ArrayCompr { term, query, .. } | SetCompr { term, query, .. } => {
+                self.partial_depends(term, state)? || self.partial_query_depends(query, state)?
+            }
+            ObjectCompr {
+                key, value, query, ..
+            } => {
+                self.partial_depends(key, state)?
+                    || self.partial_depends(value, state)?
+                    || self.partial_query_depends(query, state)?
+            }
+            Call { fcn, params, .. } => {
+                for p in params {
+                    if self.partial_depends(p, state)? {
+                        return Ok(true);
+                    }
+                }
+                match self.partial_user_function(fcn)? {
+                    Some(path) => self.partial_function_depends(&path, state)?,
+                    None => false,
+                }
+            }
+            UnaryExpr { expr, .. } => self.partial_depends(expr, state)?,
+            BinExpr { lhs, rhs, .. }
+            | BoolExpr { lhs, rhs, .. }
+            | ArithExpr { lhs, rhs, .. }
+            | AssignExpr { lhs, rhs, .. } => {
+                self.partial_depends(lhs, state)? || self.partial_depends(rhs, state)?
+            }
+            Membership {
+                key,
+                value,
+                collection,
+                ..
+            } => {
+                self.partial_depends(value, state)?
+                    || self.partial_depends(collection, state)?
+                    || match key {
+                        Some(k) => self.partial_depends(k, state)?,
+                        None => false,
+                    }
+            }
+            #[cfg(feature = "rego-extensions")]
+            OrExpr { lhs, rhs, .. } => {
+                self.partial_depends(lhs, state)? || self.partial_depends(rhs, state)?
+            }
+        })
+    }
+
+    fn partial_ref_depends(&mut self, refr: &ExprRef, state: &mut PartialState) -> Result<bool> {
+        let root = match Self::partial_root_var(refr) {
+            Some(span) => span,
+            None => return Ok(false),
+        };
+        if state.is_alias(root.text()) {
+            return Ok(true);
+        }
+        if self.lookup_local_var(&root.source_str()).is_some() {
+            return Ok(false);
+        }
+
+        let comps = Self::get_rule_path_components(refr)?;
+        let overlaps = state.unknowns.iter().any(|u| {
+            u.iter()
+                .zip(comps.iter())
+                .all(|(a, b)| a.as_str() == b.as_ref())
+        });
+        if overlaps {
+            return Ok(true);
+        }
+
+        match self.partial_rule_prefix(refr)? {
+            Some((path, _)) => self.partial_rule_depends(&path, state),
+            None => Ok(false),
+        }
+    }
+
+    fn partial_user_function(&self, fcn: &ExprRef) -> Result<Option<String>> {
+        let path = get_path_string(fcn, None)?;
+        let full_path = get_path_string(fcn, Some(&self.current_module_path))?;
+        Ok(if self.functions.contains_key(&full_path) {
+            Some(full_path)
+        } else if self.functions.contains_key(&path) {
+            Some(path)
+        } else {
+            None
+        })
+    }
+
+    fn partial_function_depends(&mut self, path: &str, state: &mut PartialState) -> Result<bool> {
+        let key = path.to_string() + "()";
+        if let Some(d) = state.rules.get(&key) {
+            return Ok(*d);
+        }
+        state.rules.insert(key.clone(), false);
+        let rules = match self.functions.get(path) {
+            Some((rules, _, _)) => rules.clone(),
+            None => vec![],
+        };
+        let mut depends = false;
+        for rule in rules {
+            if self.partial_rule_body_depends(&rule, state)? {
+                depends = true;
+                break;
+            }
+        }
+        state.rules.insert(key, depends);
+        Ok(depends)
+    }
+
+    fn partial_value_literal(v: &Value) -> String {
+        match v {
+            Value::Array(a) => format!(
+                "[{}]",
+                a.iter()
+                    .map(Self::partial_value_literal)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Value::Set(s) if s.is_empty() => "set()".to_string(),
+            Value::Set(s) => format!(
+                "{{{}}}",
+                s.iter()
+                    .map(Self::partial_value_literal)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Value::Object(fields) => format!(
+                "{{{}}}",
+                fields
+                    .iter()
+                    .map(|(k, v)| format!(
+                        "{}: {}",
+                        Self::partial_value_literal(k),
+                        Self::partial_value_literal(v)
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            _ => v.to_string(),
+        }
+    }
+
+    // Residual of an operand. Compound operands are parenthesized.
+    fn partial_operand(
+        &mut self,
+        expr: &ExprRef,
+        state: &mut PartialState,
+    ) -> Result<Option<String>> {
+        let r = self.partial_residual(expr, state)?;
+        Ok(match expr.as_ref() {
+            Expr::BoolExpr { .. }
+            | Expr::ArithExpr { .. }
+            | Expr::BinExpr { .. }
+            | Expr::Membership { .. }
+                if self.partial_depends(expr, state)? =>
+            {
+                r.map(|r| format!("({r})"))
+            }
+            _ => r,
+        })
+    }
+
+    fn partial_residuals(
+        &mut self,
+        exprs: &[ExprRef],
+        state: &mut PartialState,
+    ) -> Result<Option<Vec<String>>> {
+        let mut residuals = vec![];
+        for e in exprs {
+            match self.partial_residual(e, state)? {
+                Some(r) => residuals.push(r),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(residuals))
+    }
+
+    // Produce the residual text of an expression. Known sub-expressions are replaced by their
+    // values. None is returned if a known sub-expression is undefined.
+    fn partial_residual(
+        &mut self,
+        expr: &ExprRef,
+        state: &mut PartialState,
+    ) -> Result<Option<String>> {
+        use Expr::*;
+        if !self.partial_depends(expr, state)? {
+            return Ok(match self.eval_expr(expr)? {
+                Value::Undefined => None,
+                v => Some(Self::partial_value_literal(&v)),
+            });
+        }
+
+        macro_rules! residual {
+            ($e:expr) => {
+                match self.partial_residual($e, state)? {
+                    Some(r) => r,
+                    None => return Ok(None),
+                }
+            };
+        }
+        macro_rules! operand {
+            ($e:expr) => {
+                match self.partial_operand($e, state)? {
+                    Some(r) => r,
+                    None => return Ok(None),
+                }
+            };
+        }
+
+        if matches!(expr.as_ref(), Var(_) | RefDot { .. } | RefBrack { .. }) {
+            if let Some((path, _)) = self.partial_rule_prefix(expr)? {
+                return Err(Self::partial_unsupported(
+                    expr.span(),
+                    &format!("reference to rule `{path}`"),
+                ));
+            }
+        }
+
+        Ok(Some(match expr.as_ref() {
+            Var((span, _)) => match state.alias(span.text()) {
+                Some(r) => r.clone(),
+                None => span.text().to_string(),
+            },
+            RefDot { refr, field, .. } => format!("{}.{}", residual!(refr), field.0.text()),
+            RefBrack { refr, index, .. } => format!("{}[{}]", residual!(refr), residual!(index)),
+            Array { items, .. } => match self.partial_residuals(items, state)? {
+                Some(items) => format!("[{}]", items.join(", ")),
+                None => return Ok(None),
+            },
+            Set { items, .. } => match self.partial_residuals(items, state)? {
+                Some(items) => format!("{{{}}}", items.join(", ")),
+                None => return Ok(None),
+            },
+            Object { fields, .. } => {
+                let mut items = vec![];
+                for (_, k, v) in fields {
+                    items.push(format!("{}: {}", residual!(k), residual!(v)));
+                }
+                format!("{{{}}}", items.join(", "))
+            }
+            Call { span, fcn, params } => {
+                if self.partial_user_function(fcn)?.is_some() {
+                    return Err(Self::partial_unsupported(span, "function call"));
+                }
+                match self.partial_residuals(params, state)? {
+                    Some(params) => {
+                        format!("{}({})", get_path_string(fcn, None)?, params.join(", "))
+                    }
+                    None => return Ok(None),
+                }
+            }
+            UnaryExpr { expr, .. } => format!("-{}", operand!(expr)),
+            BinExpr { op, lhs, rhs, .. } => {
+                let op = match op {
+                    BinOp::Union => "|",
+                    BinOp::Intersection => "&",
+                };
+                format!("{} {op} {}", operand!(lhs), operand!(rhs))
+            }
+            BoolExpr { op, lhs, rhs, .. } => {
+                let op = match op {
+                    BoolOp::Lt => "<",
+                    BoolOp::Le => "<=",
+                    BoolOp::Eq => "==",
+                    BoolOp::Ge => ">=",
+                    BoolOp::Gt => ">",
+                    BoolOp::Ne => "!=",
+                };
+                format!("{} {op} {}", operand!(lhs), operand!(rhs))
+            }
+            ArithExpr { op, lhs, rhs, .. } => {
+                let op = match op {
+                    ArithOp::Add => "+",
+                    ArithOp::Sub => "-",
+                    ArithOp::Mul => "*",
+                    ArithOp::Div => "/",
+                    ArithOp::Mod => "%",
+                };
+                format!("{} {op} {}", operand!(lhs), operand!(rhs))
+            }
+            AssignExpr { op, lhs, rhs, .. } => {
+                let op = match op {
+                    AssignOp::Eq => "=",
+                    AssignOp::ColEq => ":=",
+                };
+                format!("{} {op} {}", operand!(lhs), operand!(rhs))
+            }
+            Membership {
+                key,
+                value,
+                collection,
+                ..
+            } => {
+                let key = match key {
+                    Some(k) => format!("{}, ", operand!(k)),
+                    None => "".to_string(),
+                };
+                format!("{key}{} in {}", operand!(value), operand!(collection))
+            }
+            _ => return Err(Self::partial_unsupported(expr.span(), "expression")),
+        }))
+    }
+
     fn get_rule_path_components(mut refr: &Ref<Expr>) -> Result<Vec<Rc<str>>> {
         let mut components: Vec<Rc<str>> = vec![];
         loop {
@@ -3725,6 +4783,19 @@ impl Interpreter {
         nargs: u8,
         extension: Box<dyn Extension>,
     ) -> Result<()> {
+        if matches!(
+            path.as_str(),
+            "print" | "rego.metadata.rule" | "rego.metadata.chain"
+        ) || builtins::BUILTINS.contains_key(path.as_str())
+        {
+            bail!("extension `{path}` conflicts with builtin of the same name");
+        }
+
+        #[cfg(feature = "deprecated")]
+        if builtins::DEPRECATED.contains_key(path.as_str()) {
+            bail!("extension `{path}` conflicts with builtin of the same name");
+        }
+
         if let MapEntry::Vacant(v) = self.extensions.entry(path) {
             v.insert((nargs, Rc::new(extension)));
             Ok(())
@@ -3826,6 +4897,10 @@ impl Interpreter {
                             }
                         }
                         for b in bodies {
+                            // Values of else branches.
+                            if let Some(a) = &b.assign {
+                                self.gather_coverage_in_expr(&a.value, covered, &mut file)?;
+                            }
                             self.gather_coverage_in_query(&b.query, covered, &mut file)?;
                         }
                     }
@@ -3862,13 +4937,31 @@ impl Interpreter {
         self.gather_prints = b;
     }
 
+    pub fn set_enable_explanation(&mut self, enable: bool) {
+        self.explanation = match enable {
+            true => Some(self.explanation.take().unwrap_or_default()),
+            false => None,
+        };
+    }
+
+    pub fn take_explanation(&mut self) -> Result<Vec<TraceEvent>> {
+        match &mut self.explanation {
+            Some(events) => Ok(core::mem::take(events)),
+            None => bail!("explanation is not enabled"),
+        }
+    }
+
+    pub fn set_print_hook(&mut self, hook: Option<Box<dyn PrintHook>>) {
+        self.print_hook = hook.map(Rc::from);
+    }
+
     pub fn take_prints(&mut self) -> Result<Vec<String>> {
         Ok(core::mem::take(&mut self.prints))
     }
 
     pub fn eval_rule_in_path(&mut self, path: String) -> Result<Value> {
         if !self.rule_paths.contains(&path) {
-            bail!("not a valid rule path");
+            bail!("`{path}` is not a valid rule path");
         }
         self.ensure_rule_evaluated(path.clone())?;
         let parts: Vec<&str> = path.split('.').collect();
@@ -108,6 +108,19 @@ impl SourceStr {
             end: 0,
         }
     }
+
+    pub fn source(&self) -> &Source {
+        &self.source
+    }
+
+    /// Line and column (both starting at 1) of the start of the string.
+    pub fn line_col(&self) -> (u32, u32) {
+        let prefix = &self.source.contents()[..self.start as usize];
+        let line = prefix.matches('\n').count() + 1;
+        let line_start = prefix.rfind('\n').map_or(0, |i| i + 1);
+        let col = prefix[line_start..].chars().count() + 1;
+        (line as u32, col as u32)
+    }
 }
 
 impl cmp::PartialEq for SourceStr {
@@ -196,6 +209,23 @@ impl Source {
         }
     }
 
+    /// Span of the given line (starting at 1), excluding the line terminator.
+    pub fn line_span(&self, line: u32) -> Span {
+        let (start, end) = self
+            .src
+            .lines
+            .get(line as usize - 1)
+            .copied()
+            .unwrap_or_default();
+        Span {
+            source: self.clone(),
+            line,
+            col: 1,
+            start,
+            end,
+        }
+    }
+
     pub fn message(&self, line: u32, col: u32, kind: &str, msg: &str) -> String {
         if line as usize > self.src.lines.len() {
             return format!("{}: invalid line {} specified", self.src.file, line);
@@ -226,6 +256,34 @@ impl Source {
     pub fn error(&self, line: u32, col: u32, msg: &str) -> anyhow::Error {
         anyhow!(self.message(line, col, "error", msg))
     }
+
+    pub(crate) fn syntax_error(&self, line: u32, col: u32, msg: &str) -> anyhow::Error {
+        anyhow::Error::msg(SourceError {
+            line,
+            col,
+            range: None,
+            message: msg.to_string(),
+            rendered: self.message(line, col, "error", msg),
+        })
+    }
+}
+
+// Syntax error at a location within a source. The location is retained so that the parser can
+// produce diagnostics without parsing the rendered message.
+#[derive(Debug, Clone)]
+pub struct SourceError {
+    pub line: u32,
+    pub col: u32,
+    // Byte range of the offending text, if known.
+    pub range: Option<(u32, u32)>,
+    pub message: String,
+    rendered: String,
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.rendered)
+    }
 }
 
 #[derive(Clone)]
@@ -255,6 +313,16 @@ impl Span {
     pub fn error(&self, msg: &str) -> anyhow::Error {
         self.source.error(self.line, self.col, msg)
     }
+
+    pub(crate) fn syntax_error(&self, msg: &str) -> anyhow::Error {
+        anyhow::Error::msg(SourceError {
+            line: self.line,
+            col: self.col,
+            range: Some((self.start, self.end)),
+            message: msg.to_string(),
+            rendered: self.message("error", msg),
+        })
+    }
 }
 
 impl Debug for Span {
@@ -412,7 +480,9 @@ impl<'source> Lexer<'source> {
         // these characters:
         let ch = self.peek().1;
         if ch == '_' || ch == '.' || ch.is_ascii_alphanumeric() {
-            return Err(self.source.error(self.line, self.col, "invalid number"));
+            return Err(self
+                .source
+                .syntax_error(self.line, self.col, "invalid number"));
         }
 
         // Ensure that the number is parsable in Rust.
@@ -430,7 +500,7 @@ impl<'source> Lexer<'source> {
 
                 bail!(
                     "{} {}",
-                    self.source.error(
+                    self.source.syntax_error(
                         self.line,
                         col,
                         "invalid number. serde_json cannot parse number:"
@@ -466,7 +536,7 @@ impl<'source> Lexer<'source> {
                     break;
                 }
                 '\x00' => {
-                    return Err(self.source.error(line, col, "unmatched `"));
+                    return Err(self.source.syntax_error(line, col, "unmatched `"));
                 }
                 '\t' => self.col += 4,
                 '\n' => {
@@ -514,7 +584,7 @@ impl<'source> Lexer<'source> {
                                 let (offset, ch) = self.peek();
                                 let col = self.col + (offset - start) as u32;
                                 if !ch.is_ascii_hexdigit() {
-                                    return Err(self.source.error(
+                                    return Err(self.source.syntax_error(
                                         line,
                                         col,
                                         "invalid hex escape sequence",
@@ -523,14 +593,24 @@ impl<'source> Lexer<'source> {
                                 self.iter.next();
                             }
                         }
-                        _ => return Err(self.source.error(line, col, "invalid escape sequence")),
+                        _ => {
+                            return Err(self.source.syntax_error(
+                                line,
+                                col,
+                                "invalid escape sequence",
+                            ))
+                        }
                     }
                 }
                 _ => {
                     // check for valid json chars
                     let col = self.col + (offset - start) as u32;
                     if !('\u{0020}'..='\u{10FFFF}').contains(&ch) {
-                        return Err(self.source.error(line, col, "invalid character in string"));
+                        return Err(self.source.syntax_error(
+                            line,
+                            col,
+                            "invalid character in string",
+                        ));
                     }
                     self.iter.next();
                 }
@@ -538,7 +618,7 @@ impl<'source> Lexer<'source> {
         }
 
         if self.peek().1 != '"' {
-            return Err(self.source.error(line, col, "unmatched \""));
+            return Err(self.source.syntax_error(line, col, "unmatched \""));
         }
 
         self.iter.next();
@@ -554,7 +634,7 @@ impl<'source> Lexer<'source> {
                 bail!(
                     "{} {}",
                     self.source
-                        .error(self.line, col, "serde_json cannot parse string:"),
+                        .syntax_error(self.line, col, "serde_json cannot parse string:"),
                     msg
                 )
             }
@@ -595,7 +675,7 @@ impl<'source> Lexer<'source> {
                 '\t' => self.col += 4,
                 '\r' => {
                     if self.peekahead(1).1 != '\n' {
-                        return Err(self.source.error(
+                        return Err(self.source.syntax_error(
                             self.line,
                             self.col,
                             "\\r must be followed by \\n",
@@ -741,7 +821,7 @@ impl<'source> Lexer<'source> {
 		    end: start as u32 + 1,
 		}))
 	    }
-	    _ => Err(self.source.error(self.line, self.col, "invalid character"))
+	    _ => Err(self.source.syntax_error(self.line, self.col, "invalid character"))
 	}
     }
 }
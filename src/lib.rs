@@ -32,7 +32,7 @@ mod value;
 
 pub use engine::Engine;
 pub use lexer::Source;
-pub use value::Value;
+pub use value::{Value, ValueLimits};
 
 #[cfg(feature = "arc")]
 use alloc::sync::Arc as Rc;
@@ -333,6 +333,102 @@ pub struct QueryResults {
     pub result: Vec<QueryResult>,
 }
 
+/// Kind of a [`TraceEvent`].
+#[derive(Debug, Clone, Copy, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceOp {
+    /// A rule or function is being evaluated. The message is the path of the rule.
+    Enter,
+    /// A statement is being evaluated. The message is the text of the statement.
+    Eval,
+    /// A variable has been bound. The message is `<var> = <value>`.
+    Bind,
+    /// A `trace` call. The message is the traced string.
+    Note,
+}
+
+/// An event recorded while evaluating with explanations enabled.
+///
+/// See [`Engine::set_enable_explanation`].
+#[derive(Debug, Clone, Serialize, Eq, PartialEq)]
+pub struct TraceEvent {
+    /// Kind of event.
+    pub op: TraceOp,
+    /// The policy file (or query) the event pertains to.
+    pub file: String,
+    /// Location of the rule, statement, variable or `trace` call.
+    pub location: Location,
+    /// Description of the event.
+    pub message: String,
+}
+
+/// Result of partially evaluating a query.
+///
+/// See [`Engine::partial_eval`].
+#[derive(Debug, Clone, Default, Serialize, Eq, PartialEq)]
+pub struct PartialResult {
+    /// Residual queries over the unknowns. The original query holds if any of the residual
+    /// queries holds; each residual query is a conjunction of Rego expressions.
+    ///
+    /// No residual queries means that the query is always false or undefined. A residual
+    /// query with no expressions means that the query always holds.
+    pub queries: Vec<Vec<String>>,
+}
+
+/// A problem reported by strict compilation.
+///
+/// See [`Engine::set_strict`].
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// Description of the problem.
+    pub message: String,
+    /// Location of the offending variable or import.
+    pub span: lexer::Span,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.span.message("error", &self.message))
+    }
+}
+
+/// Error returned when an evaluation exceeds the step budget.
+///
+/// See [`Engine::set_max_steps`]. The error can be recovered from the error returned by
+/// evaluation via [`anyhow::Error::downcast_ref`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BudgetExceeded {
+    /// The step budget that was exceeded.
+    pub max_steps: u64,
+    /// Path of the policy file being evaluated when the budget was exceeded.
+    pub file: String,
+    /// Line of the expression being evaluated when the budget was exceeded.
+    pub line: u32,
+    /// Column of the expression being evaluated when the budget was exceeded.
+    pub col: u32,
+    /// Error message pointing at the expression.
+    pub message: String,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Error returned when an evaluation is cancelled.
+///
+/// See [`Engine::set_cancellation_flag`]. The error can be recovered from the error returned
+/// by evaluation via [`anyhow::Error::downcast_ref`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("evaluation cancelled")
+    }
+}
+
 /// A user defined builtin function implementation.
 ///
 /// It is not necessary to implement this trait directly.
@@ -370,6 +466,98 @@ impl fmt::Debug for dyn Extension {
     }
 }
 
+/// Host name resolver used by the `net.lookup_ip_addr` builtin.
+///
+/// DNS lookups are non-deterministic and have side effects. Therefore no resolver is
+/// configured by default and `net.lookup_ip_addr` raises an error unless one has been
+/// set via [`Engine::set_resolver`]. Closures with a matching signature are valid resolvers.
+#[cfg(feature = "net")]
+#[cfg_attr(docsrs, doc(cfg(feature = "net")))]
+pub trait Resolver: Send + Sync {
+    /// Returns the IP addresses that `name` resolves to.
+    fn lookup_ip_addr(&self, name: &str) -> anyhow::Result<Vec<core::net::IpAddr>>;
+}
+
+#[cfg(feature = "net")]
+impl<F> Resolver for F
+where
+    F: Fn(&str) -> anyhow::Result<Vec<core::net::IpAddr>> + Send + Sync,
+{
+    fn lookup_ip_addr(&self, name: &str) -> anyhow::Result<Vec<core::net::IpAddr>> {
+        self(name)
+    }
+}
+
+#[cfg(feature = "net")]
+impl fmt::Debug for dyn Resolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> core::result::Result<(), fmt::Error> {
+        f.write_fmt(format_args!("<resolver>"))
+    }
+}
+
+/// [`Resolver`] that uses the operating system's resolver.
+#[cfg(all(feature = "net", feature = "std"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "net", feature = "std"))))]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemResolver;
+
+#[cfg(all(feature = "net", feature = "std"))]
+impl Resolver for SystemResolver {
+    fn lookup_ip_addr(&self, name: &str) -> anyhow::Result<Vec<core::net::IpAddr>> {
+        use std::net::ToSocketAddrs;
+        Ok((name, 0).to_socket_addrs()?.map(|a| a.ip()).collect())
+    }
+}
+
+/// Loader of policies for packages that are imported but not yet loaded.
+///
+/// Set via [`Engine::set_module_resolver`]. Closures with a matching signature are valid
+/// resolvers.
+pub trait ModuleResolver: Send + Sync {
+    /// Returns the policy that defines `package` (e.g. `data.lib.utils`) as a pair of file name
+    /// and Rego source, or `None` if the package is unknown to the resolver.
+    fn resolve(&self, package: &str) -> anyhow::Result<Option<(String, String)>>;
+}
+
+impl<F> ModuleResolver for F
+where
+    F: Fn(&str) -> anyhow::Result<Option<(String, String)>> + Send + Sync,
+{
+    fn resolve(&self, package: &str) -> anyhow::Result<Option<(String, String)>> {
+        self(package)
+    }
+}
+
+impl fmt::Debug for dyn ModuleResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> core::result::Result<(), fmt::Error> {
+        f.write_fmt(format_args!("<module resolver>"))
+    }
+}
+
+/// Sink for the output of `print` statements.
+///
+/// Set via [`Engine::set_print_hook`]. Closures with a matching signature are valid hooks.
+pub trait PrintHook: Send + Sync {
+    /// Called with the output of a `print` statement, prefixed with its location
+    /// (`<file>:<line>: <message>`).
+    fn print(&self, msg: &str);
+}
+
+impl<F> PrintHook for F
+where
+    F: Fn(&str) + Send + Sync,
+{
+    fn print(&self, msg: &str) {
+        self(msg)
+    }
+}
+
+impl fmt::Debug for dyn PrintHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> core::result::Result<(), fmt::Error> {
+        f.write_fmt(format_args!("<print hook>"))
+    }
+}
+
 #[cfg(feature = "coverage")]
 #[cfg_attr(docsrs, doc(cfg(feature = "coverage")))]
 pub mod coverage {
@@ -54,6 +54,10 @@ impl BigDecimal {
     }
 }
 
+/// An arbitrary precision decimal number.
+///
+/// Addition, subtraction and multiplication are exact. Division is truncated
+/// to 100 significant digits, so `1 / 3 * 3` is not exactly `1`.
 #[derive(Clone)]
 pub enum Number {
     // TODO: maybe specialize for u64, i64, f64
@@ -348,14 +352,22 @@ impl Number {
 
     pub fn lsh(&self, rhs: &Self) -> Option<Number> {
         match Self::ensure_integers(self, rhs) {
-            Some((a, b)) => a.checked_shl(b as u32).map(|v| v.into()),
+            Some((a, b)) => {
+                let b = u32::try_from(b).ok()?;
+                // checked_shl only fails for b >= 128, not when bits are shifted out.
+                match a.checked_shl(b) {
+                    Some(v) if v >> b == a => Some(v.into()),
+                    _ => None,
+                }
+            }
             _ => None,
         }
     }
 
     pub fn rsh(&self, rhs: &Self) -> Option<Number> {
         match Self::ensure_integers(self, rhs) {
-            Some((a, b)) => a.checked_shr(b as u32).map(|v| v.into()),
+            // Shifting by 127 or more leaves only the sign, i.e. 0 or -1.
+            Some((a, b)) => Some((a >> u32::try_from(b).ok()?.min(BigInt::BITS - 1)).into()),
             _ => None,
         }
     }
@@ -372,21 +384,33 @@ impl Number {
 
     pub fn floor(&self) -> Number {
         match self {
-            Big(b) => Big(BigDecimal::from(b.d.round(
-                scientific::Precision::Decimals(0),
-                scientific::Rounding::RoundDown,
-            ))
-            .into()),
+            Big(b) => {
+                let mut f = b.d.round(
+                    scientific::Precision::Decimals(0),
+                    scientific::Rounding::RoundDown,
+                );
+                // scientific rounds magnitudes below 0.1 to zero regardless of direction.
+                if f > b.d {
+                    f = &f - &BigFloat::from(1);
+                }
+                Big(BigDecimal::from(f).into())
+            }
         }
     }
 
     pub fn ceil(&self) -> Number {
         match self {
-            Big(b) => Big(BigDecimal::from(b.d.round(
-                scientific::Precision::Decimals(0),
-                scientific::Rounding::RoundUp,
-            ))
-            .into()),
+            Big(b) => {
+                let mut c = b.d.round(
+                    scientific::Precision::Decimals(0),
+                    scientific::Rounding::RoundUp,
+                );
+                // scientific rounds magnitudes below 0.1 to zero regardless of direction.
+                if c < b.d {
+                    c = &c + &BigFloat::from(1);
+                }
+                Big(BigDecimal::from(c).into())
+            }
         }
     }
 
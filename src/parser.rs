@@ -73,7 +73,7 @@ impl<'source> Parser<'source> {
             self.next_token()
         } else {
             let msg = format!("expecting `{text}` {context}");
-            Err(self.source.error(self.tok.1.line, self.tok.1.col, &msg))
+            Err(self.tok.1.syntax_error(&msg))
         }
     }
 
@@ -99,15 +99,10 @@ impl<'source> Parser<'source> {
 
     pub fn set_future_keyword(&mut self, kw: &str, span: &Span) -> Result<()> {
         match &self.future_keywords.get(kw) {
-            Some(s) if self.rego_v1 => Err(self.source.error(
-                span.line,
-                span.col,
-                format!(
-                    "this import shadows previous import of `{kw}` defined at:{}",
-                    s.message("", "this import is shadowed.")
-                )
-                .as_str(),
-            )),
+            Some(s) if self.rego_v1 => Err(span.syntax_error(&format!(
+                "this import shadows previous import of `{kw}` defined at:{}",
+                s.message("", "this import is shadowed.")
+            ))),
             _ => {
                 self.future_keywords.insert(kw.to_string(), span.clone());
                 if kw == "every" && !self.rego_v1 {
@@ -164,17 +159,21 @@ impl<'source> Parser<'source> {
                 }
                 _ => {
                     let s = &comps[3];
-                    return Err(self
-                        .source
-                        .error(s.line, s.col - 1, "invalid future keyword"));
+                    return Err(self.source.syntax_error(
+                        s.line,
+                        s.col - 1,
+                        "invalid future keyword",
+                    ));
                 }
             }
             Ok(true)
         } else if !comps.is_empty() && comps[0].text() == "future" {
             let s = &comps[0];
-            Err(self
-                .source
-                .error(s.line, s.col, "invalid import, must be `future.keywords`"))
+            Err(self.source.syntax_error(
+                s.line,
+                s.col,
+                "invalid import, must be `future.keywords`",
+            ))
         } else {
             Ok(false)
         }
@@ -222,18 +221,19 @@ impl<'source> Parser<'source> {
     fn parse_ident(&mut self) -> Result<Span> {
         let span = self.tok.1.clone();
         match self.tok.0 {
-            TokenKind::Ident if self.is_keyword(span.text()) => Err(self.source.error(
-                self.tok.1.line,
-                self.tok.1.col,
-                &format!("unexpected keyword `{}`", span.text()),
-            )),
+            TokenKind::Ident if self.is_keyword(span.text()) => Err(self
+                .tok
+                .1
+                .syntax_error(&format!("unexpected keyword `{}`", span.text()))),
             TokenKind::Ident => {
                 self.next_token()?;
                 Ok(span)
             }
-            _ => Err(self
-                .source
-                .error(self.tok.1.line, self.tok.1.col, "expecting identifier")),
+            _ => Err(self.source.syntax_error(
+                self.tok.1.line,
+                self.tok.1.col,
+                "expecting identifier",
+            )),
         }
     }
 
@@ -246,26 +246,27 @@ impl<'source> Parser<'source> {
 		    // contains can be the name of a builtin even when a keyword
 		    && span.text() != "contains") =>
             {
-                Err(self.source.error(
-                    self.tok.1.line,
-                    self.tok.1.col,
-                    &format!("unexpected keyword `{}`", span.text()),
-                ))
+                Err(self
+                    .tok
+                    .1
+                    .syntax_error(&format!("unexpected keyword `{}`", span.text())))
             }
             TokenKind::Ident => {
                 self.next_token()?;
                 Ok(span)
             }
-            _ => Err(self
-                .source
-                .error(self.tok.1.line, self.tok.1.col, "expecting identifier")),
+            _ => Err(self.source.syntax_error(
+                self.tok.1.line,
+                self.tok.1.col,
+                "expecting identifier",
+            )),
         }
     }
 
     fn read_number(span: Span) -> Result<Expr> {
         match Number::from_str(span.text()) {
             Ok(v) => Ok(Expr::Number((span, Value::Number(v)))),
-            Err(_) => bail!(span.error("could not parse number")),
+            Err(_) => bail!(span.syntax_error("could not parse number")),
         }
     }
 
@@ -277,7 +278,9 @@ impl<'source> Parser<'source> {
                 let v = match serde_json::from_str::<Value>(format!("\"{}\"", span.text()).as_str())
                 {
                     Ok(v) => v,
-                    Err(e) => bail!(span.error(format!("invalid string literal. {e}").as_str())),
+                    Err(e) => {
+                        bail!(span.syntax_error(format!("invalid string literal. {e}").as_str()))
+                    }
                 };
                 Expr::String((span, v))
             }
@@ -295,13 +298,7 @@ impl<'source> Parser<'source> {
                     return Ok(Expr::Var((ident, v)));
                 }
             },
-            _ => {
-                return Err(self.source.error(
-                    self.tok.1.line,
-                    self.tok.1.col,
-                    "expecting expression",
-                ))
-            }
+            _ => return Err(self.tok.1.syntax_error("expecting expression")),
         };
         self.next_token()?;
         Ok(node)
@@ -557,7 +554,7 @@ impl<'source> Parser<'source> {
                     }
                     bail!(
                         "{}",
-                        self.source.error(
+                        self.source.syntax_error(
                             self.tok.1.line,
                             self.tok.1.col,
                             format!("invalid whitespace before {}", self.token_text()).as_str()
@@ -574,7 +571,7 @@ impl<'source> Parser<'source> {
                     if field.start != sep_pos + 1 {
                         bail!(
                             "{}",
-                            self.source.error(
+                            self.source.syntax_error(
                                 field.line,
                                 field.col - 1,
                                 "invalid whitespace between . and identifier"
@@ -866,10 +863,10 @@ impl<'source> Parser<'source> {
             ":=" if self.rego_v1 => {
                 if let Expr::Var(v) = &expr {
                     if v.0.text() == "input" {
-                        bail!(span.error("input cannot be shadowed"));
+                        bail!(span.syntax_error("input cannot be shadowed"));
                     }
                     if v.0.text() == "data" {
-                        bail!(span.error("data cannot be shadowed"));
+                        bail!(span.syntax_error("data cannot be shadowed"));
                     }
                 }
                 AssignOp::ColEq
@@ -922,11 +919,9 @@ impl<'source> Parser<'source> {
                 match self.parse_var() {
                     Ok(v) => (Some(ident), v),
                     Err(e) => {
-                        return Err(self.source.error(
-                            span.line,
-                            span.col,
-                            format!("Failed to parse `every` statement.\n{e}").as_str(),
-                        ))
+                        return Err(
+                            span.syntax_error(&format!("Failed to parse `every` statement.\n{e}"))
+                        )
                     }
                 }
             }
@@ -1094,7 +1089,7 @@ impl<'source> Parser<'source> {
                 // There was error parsing the first literal
                 // Restore the state and return.
                 *self = state;
-                bail!(span.error(format!("expecting {end_delim}").as_str()));
+                bail!(span.syntax_error(format!("expecting {end_delim}").as_str()));
             }
         };
 
@@ -1175,7 +1170,7 @@ impl<'source> Parser<'source> {
                 "." | "[" if self.tok.1.start != self.end => {
                     bail!(
                         "{}",
-                        self.source.error(
+                        self.source.syntax_error(
                             self.tok.1.line,
                             self.tok.1.col - 1,
                             format!("invalid whitespace before {}", self.token_text()).as_str()
@@ -1192,7 +1187,7 @@ impl<'source> Parser<'source> {
                     if field.start != sep_pos + 1 {
                         bail!(
                             "{}",
-                            self.source.error(
+                            self.source.syntax_error(
                                 field.line,
                                 field.col - 1,
                                 "invalid whitespace between . and identifier"
@@ -1210,11 +1205,7 @@ impl<'source> Parser<'source> {
                     let index = match &self.tok.0 {
                         TokenKind::String => Expr::String(Self::span_and_value(self.tok.1.clone())),
                         _ => {
-                            return Err(self.source.error(
-                                self.tok.1.line,
-                                self.tok.1.col,
-                                "expected string",
-                            ));
+                            return Err(self.tok.1.syntax_error("expected string"));
                         }
                     };
                     self.next_token()?;
@@ -1241,19 +1232,15 @@ impl<'source> Parser<'source> {
             let v = self.parse_var()?;
             if self.rego_v1 {
                 if v.text() == "input" {
-                    bail!(span.error("input cannot be shadowed"));
+                    bail!(span.syntax_error("input cannot be shadowed"));
                 }
                 if v.text() == "data" {
-                    bail!(span.error("data cannot be shadowed"));
+                    bail!(span.syntax_error("data cannot be shadowed"));
                 }
             }
             Expr::Var(Self::span_and_value(v))
         } else {
-            return Err(self.source.error(
-                span.line,
-                span.col,
-                "expecting identifier. Failed to parse rule-ref.",
-            ));
+            return Err(span.syntax_error("expecting identifier. Failed to parse rule-ref."));
         };
 
         loop {
@@ -1264,7 +1251,7 @@ impl<'source> Parser<'source> {
                 "." | "[" if self.tok.1.start != self.end => {
                     bail!(
                         "{}",
-                        self.source.error(
+                        self.source.syntax_error(
                             self.tok.1.line,
                             self.tok.1.col - 1,
                             format!("invalid whitespace before {}", self.token_text()).as_str()
@@ -1281,7 +1268,7 @@ impl<'source> Parser<'source> {
                     if field.start != sep_pos + 1 {
                         bail!(
                             "{}",
-                            self.source.error(
+                            self.source.syntax_error(
                                 field.line,
                                 field.col - 1,
                                 "invalid whitespace between . and identifier"
@@ -1442,7 +1429,7 @@ impl<'source> Parser<'source> {
             }
             "{" => {
                 if self.rego_v1 {
-                    bail!(span.error("`if` keyword is required before rule body"));
+                    bail!(span.syntax_error("`if` keyword is required before rule body"));
                 }
                 self.next_token()?;
                 let query = Ref::new(self.parse_query(span.clone(), "}")?);
@@ -1486,13 +1473,7 @@ impl<'source> Parser<'source> {
             let mut span = self.tok.1.clone();
 
             match self.token_text() {
-                "{" => {
-                    return Err(self.source.error(
-                        self.tok.1.line,
-                        self.tok.1.col,
-                        "expected `else` keyword",
-                    ))
-                }
+                "{" => return Err(self.tok.1.syntax_error("expected `else` keyword")),
                 "else" => self.next_token()?,
                 _ => break,
             }
@@ -1512,7 +1493,7 @@ impl<'source> Parser<'source> {
                 }
                 "{" => {
                     if self.rego_v1 {
-                        bail!(span.error("`if` keyword is required before rule body"));
+                        bail!(span.syntax_error("`if` keyword is required before rule body"));
                     }
                     self.next_token()?;
                     let query = Ref::new(self.parse_query(span.clone(), "}")?);
@@ -1527,11 +1508,10 @@ impl<'source> Parser<'source> {
                     if self.token_text() == "if" {
                         self.warn_future_keyword();
                     }
-                    return Err(self.source.error(
-                        self.tok.1.line,
-                        self.tok.1.col,
-                        "expected assignment or query after `else`",
-                    ));
+                    return Err(self
+                        .tok
+                        .1
+                        .syntax_error("expected assignment or query after `else`"));
                 }
                 _ => {
                     let mut query_span = span.clone();
@@ -1553,7 +1533,7 @@ impl<'source> Parser<'source> {
         Ok(())
     }
 
-    pub fn parse_default_rule(&mut self) -> Result<Rule> {
+    pub fn parse_default_rule(&mut self, annotation: Option<Ref<Annotation>>) -> Result<Rule> {
         let mut span = self.tok.1.clone();
         self.expect("default", "while parsing default rule")?;
         let rule_ref = Ref::new(self.parse_rule_ref()?);
@@ -1565,7 +1545,7 @@ impl<'source> Parser<'source> {
                 loop {
                     let arg = self.parse_ident()?;
                     if arg.text() != "_" && args.iter().any(|a: &Span| *a.text() == *arg.text()) {
-                        bail!(arg.error("repeating parameter name"));
+                        bail!(arg.syntax_error("repeating parameter name"));
                     }
                     args.push(arg);
                     if self.token_text() == ")" || self.tok.0 == TokenKind::Eof {
@@ -1601,12 +1581,14 @@ impl<'source> Parser<'source> {
                 .collect(),
             op,
             value,
+            annotation,
         })
     }
 
     pub fn parse_rule(&mut self) -> Result<Rule> {
+        let annotation = self.parse_annotation(self.tok.1.line, "rule")?;
         let pos = self.end;
-        match self.parse_default_rule() {
+        match self.parse_default_rule(annotation.clone()) {
             Ok(r) => return Ok(r),
             Err(e) if pos != self.end => return Err(e),
             _ => (),
@@ -1622,33 +1604,185 @@ impl<'source> Parser<'source> {
                 RuleHead::Compr { assign, .. } | RuleHead::Func { assign, .. }
                     if assign.is_none() =>
                 {
-                    bail!(span.error("rule must have a body or assignment"));
+                    bail!(span.syntax_error("rule must have a body or assignment"));
                 }
                 RuleHead::Set { refr, key, .. } if key.is_none() => {
                     if Self::get_path_ref_components(refr)?.len() == 2 {
-                        bail!(span.error("`contains` keyword is required for partial set rules"));
+                        bail!(span
+                            .syntax_error("`contains` keyword is required for partial set rules"));
                     } else {
-                        bail!(span.error("rule must have a body or assignment"));
+                        bail!(span.syntax_error("rule must have a body or assignment"));
                     }
                 }
                 _ => (),
             }
         }
 
-        Ok(Rule::Spec { span, head, bodies })
+        Ok(Rule::Spec {
+            span,
+            head,
+            bodies,
+            annotation,
+        })
     }
 
     pub fn parse_package(&mut self) -> Result<Package> {
         let mut span = self.tok.1.clone();
+        let annotation = self.parse_annotation(span.line, "package")?;
         self.expect("package", "Missing package declaration.")?;
         let name = self.parse_path_ref()?;
         span.end = self.end;
         Ok(Package {
             span,
             refr: Ref::new(name),
+            annotation,
         })
     }
 
+    // The annotation of a package or rule is the `# METADATA` comment block within the
+    // comment lines that immediately precede it.
+    fn parse_annotation(&self, line: u32, target: &str) -> Result<Option<Ref<Annotation>>> {
+        let comment = |l: u32| self.source.line(l - 1).trim_start().strip_prefix('#');
+
+        let mut first = line;
+        while first > 1 && comment(first - 1).is_some() {
+            first -= 1;
+        }
+
+        let Some(marker) = (first..line)
+            .rev()
+            .find(|l| comment(*l).map(str::trim) == Some("METADATA"))
+        else {
+            return Ok(None);
+        };
+
+        let mut yaml = String::new();
+        for l in marker + 1..line {
+            let text = comment(l).unwrap_or_default();
+            yaml.push_str(text.strip_prefix(' ').unwrap_or(text));
+            yaml.push('\n');
+        }
+
+        let span = self.source.line_span(marker);
+        let mut annotation = Annotation {
+            span,
+            yaml,
+            scope: String::default(),
+            title: None,
+            description: None,
+            custom: Value::Undefined,
+            value: Value::Undefined,
+        };
+        Self::parse_annotation_yaml(&mut annotation, target)?;
+
+        let scope = annotation.scope.as_str();
+        match (target, scope) {
+            ("rule", "rule" | "document") | ("package", "package" | "subpackages") => (),
+            (_, "rule" | "document" | "package" | "subpackages") => {
+                let expected = if target == "rule" { "package" } else { "rule" };
+                bail!(annotation.span.syntax_error(&format!(
+                    "annotation scope `{scope}` must be applied to {expected}"
+                )));
+            }
+            _ => bail!(annotation
+                .span
+                .syntax_error(&format!("invalid annotation scope `{scope}`"))),
+        }
+
+        Ok(Some(Ref::new(annotation)))
+    }
+
+    // Parse the YAML of an annotation and normalize it like OPA: the scope is made explicit
+    // and authors and related resources are converted to objects.
+    #[cfg(feature = "yaml")]
+    fn parse_annotation_yaml(annotation: &mut Annotation, target: &str) -> Result<()> {
+        let span = &annotation.span;
+        let yaml = match annotation.yaml.trim() {
+            "" => "{}",
+            _ => annotation.yaml.as_str(),
+        };
+        let mut value = match Value::from_yaml_str(yaml) {
+            Ok(Value::Null) => Value::new_object(),
+            Ok(v @ Value::Object(_)) => v,
+            Ok(_) => bail!(span.syntax_error("annotation must be an object")),
+            Err(e) => bail!(span.syntax_error(&format!("invalid annotation yaml. {e}"))),
+        };
+
+        let obj = value.as_object_mut()?;
+        let string_field =
+            |obj: &BTreeMap<Value, Value>, field: &str| match obj.get(&Value::from(field)) {
+                None => Ok(None),
+                Some(Value::String(s)) => Ok(Some(s.to_string())),
+                Some(v) => bail!(
+                    span.syntax_error(&format!("annotation {field} must be a string. Got {v}"))
+                ),
+            };
+
+        let scope = string_field(obj, "scope")?.unwrap_or_else(|| target.to_string());
+        obj.insert(Value::from("scope"), Value::from(scope.as_str()));
+        annotation.scope = scope;
+        annotation.title = string_field(obj, "title")?;
+        annotation.description = string_field(obj, "description")?;
+
+        match obj.get(&Value::from("custom")) {
+            None => (),
+            Some(c @ Value::Object(_)) => annotation.custom = c.clone(),
+            Some(c) => {
+                bail!(span.syntax_error(&format!("annotation custom must be an object. Got {c}")))
+            }
+        }
+
+        if let Some(authors) = obj.get_mut(&Value::from("authors")) {
+            let Value::Array(authors) = authors else {
+                bail!(span.syntax_error("annotation authors must be a list"));
+            };
+            for author in Rc::make_mut(authors).iter_mut() {
+                if let Value::String(s) = author {
+                    let mut a = BTreeMap::new();
+                    let (name, email) = match s.trim().strip_suffix('>') {
+                        Some(s) => match s.rsplit_once('<') {
+                            Some((name, email)) => (name.trim(), email.trim()),
+                            None => (s.trim(), ""),
+                        },
+                        None => (s.trim(), ""),
+                    };
+                    if !name.is_empty() {
+                        a.insert(Value::from("name"), Value::from(name));
+                    }
+                    if !email.is_empty() {
+                        a.insert(Value::from("email"), Value::from(email));
+                    }
+                    *author = Value::from_map(a);
+                }
+            }
+        }
+
+        if let Some(resources) = obj.get_mut(&Value::from("related_resources")) {
+            let Value::Array(resources) = resources else {
+                bail!(span.syntax_error("annotation related_resources must be a list"));
+            };
+            for resource in Rc::make_mut(resources).iter_mut() {
+                if let Value::String(_) = resource {
+                    let mut r = BTreeMap::new();
+                    r.insert(Value::from("ref"), resource.clone());
+                    *resource = Value::from_map(r);
+                }
+            }
+        }
+
+        annotation.value = value;
+        Ok(())
+    }
+
+    // Without YAML support, annotations cannot be validated. Report them rather than
+    // silently accepting malformed blocks.
+    #[cfg(not(feature = "yaml"))]
+    fn parse_annotation_yaml(annotation: &mut Annotation, _target: &str) -> Result<()> {
+        bail!(annotation
+            .span
+            .syntax_error("parsing annotations requires the `yaml` feature"))
+    }
+
     fn check_and_add_import(&self, import: Import, imports: &mut Vec<Import>) -> Result<()> {
         let ref_comps = Self::get_path_ref_components(&import.refr)?;
         let comps: Vec<&str> = ref_comps.iter().map(|s| s.text()).collect();
@@ -1669,20 +1803,10 @@ impl<'source> Parser<'source> {
             };
 
             if shadow {
-                return Err(self.source.error(
-                    import.span.line,
-                    import.span.col,
-                    format!(
-                        "import shadows following import defined earlier:{}",
-                        self.source.message(
-                            imp.span.line,
-                            imp.span.col,
-                            "",
-                            "this import is shadowed"
-                        )
-                    )
-                    .as_str(),
-                ));
+                return Err(import.span.syntax_error(&format!(
+                    "import shadows following import defined earlier:{}",
+                    imp.span.message("", "this import is shadowed")
+                )));
             }
         }
 
@@ -1700,9 +1824,7 @@ impl<'source> Parser<'source> {
             let comps = Self::get_path_ref_components(&refr)?;
             span.end = self.end;
             if !matches!(comps[0].text(), "data" | "future" | "input" | "rego") {
-                return Err(self.source.error(
-                    comps[0].line,
-                    comps[0].col,
+                return Err(comps[0].syntax_error(
                     "import path must begin with one of: {data, future, input, rego}",
                 ));
             }
@@ -1717,21 +1839,16 @@ impl<'source> Parser<'source> {
 
             let var = if self.token_text() == "as" {
                 if is_future_kw {
-                    return Err(self.source.error(
-                        self.tok.1.line,
-                        self.tok.1.col,
-                        "`future` imports cannot be aliased",
-                    ));
+                    return Err(self
+                        .tok
+                        .1
+                        .syntax_error("`future` imports cannot be aliased"));
                 }
 
                 self.next_token()?;
                 let var = self.parse_var()?;
                 if var.text() == "_" {
-                    return Err(self.source.error(
-                        var.line,
-                        var.col,
-                        "`_` cannot be used as alias",
-                    ));
+                    return Err(var.syntax_error("`_` cannot be used as alias"));
                 }
                 Some(var)
             } else {
@@ -1755,12 +1872,49 @@ impl<'source> Parser<'source> {
     }
 
     pub fn parse(&mut self) -> Result<Module> {
-        let package = self.parse_package()?;
-        let imports = self.parse_imports()?;
+        // Only the first error is reported. Use parse_with_diagnostics to get all of them.
+        self.parse_recovering()
+            .map_err(|mut errors| errors.swap_remove(0).1)
+    }
+
+    /// Parse a module, reporting all the errors instead of just the first.
+    ///
+    /// After an error in a rule, parsing resumes at the next rule.
+    pub fn parse_with_diagnostics(&mut self) -> core::result::Result<Module, Vec<Diagnostic>> {
+        self.parse_recovering().map_err(|errors| {
+            errors
+                .into_iter()
+                .map(|(span, e)| Self::make_diagnostic(span, &e))
+                .collect()
+        })
+    }
+
+    fn parse_recovering(&mut self) -> core::result::Result<Module, Vec<(Span, anyhow::Error)>> {
+        let package = self
+            .parse_package()
+            .map_err(|e| vec![(self.tok.1.clone(), e)])?;
+        let imports = self
+            .parse_imports()
+            .map_err(|e| vec![(self.tok.1.clone(), e)])?;
 
         let mut policy = vec![];
+        let mut errors = vec![];
         while self.tok.0 != TokenKind::Eof {
-            policy.push(Ref::new(self.parse_rule()?));
+            let start = (self.lexer.clone(), self.tok.clone());
+            match self.parse_rule() {
+                Ok(rule) => policy.push(Ref::new(rule)),
+                Err(e) => {
+                    errors.push((self.tok.1.clone(), e));
+                    (self.lexer, self.tok) = start;
+                    if self.skip_rule().is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
         }
 
         Ok(Module {
@@ -1771,11 +1925,67 @@ impl<'source> Parser<'source> {
         })
     }
 
+    // Skip the tokens of the current rule. The next rule is assumed to start with an identifier
+    // at the beginning of a line that is not nested within braces, brackets or parentheses.
+    fn skip_rule(&mut self) -> Result<()> {
+        let mut depth = 0;
+        loop {
+            if self.tok.0 == TokenKind::Symbol {
+                match self.token_text() {
+                    "{" | "[" | "(" => depth += 1,
+                    "}" | "]" | ")" => depth -= 1,
+                    _ => (),
+                }
+            }
+            self.next_token()?;
+            if self.tok.0 == TokenKind::Eof
+                || (depth <= 0
+                    && self.tok.0 == TokenKind::Ident
+                    && self.tok.1.line > self.line
+                    && self.token_text() != "else")
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    // Errors raised at a location carry it as a SourceError. Other errors are reported at
+    // the token where parsing failed.
+    fn make_diagnostic(failed_at: Span, e: &anyhow::Error) -> Diagnostic {
+        let Some(err) = e.downcast_ref::<SourceError>() else {
+            return Diagnostic {
+                message: e.to_string(),
+                span: failed_at,
+            };
+        };
+
+        let span = match err.range {
+            Some((start, end)) => Span {
+                source: failed_at.source.clone(),
+                line: err.line,
+                col: err.col,
+                start,
+                end,
+            },
+            // Columns cannot be mapped to byte offsets reliably. Use the whole line.
+            None => {
+                let mut span = failed_at.source.line_span(err.line);
+                span.col = err.col;
+                span
+            }
+        };
+
+        Diagnostic {
+            message: err.message.clone(),
+            span,
+        }
+    }
+
     pub fn parse_user_query(&mut self) -> Result<Ref<Query>> {
         let span = self.tok.1.clone();
         let query = Ref::new(self.parse_query(span, "")?);
         if self.tok.0 != TokenKind::Eof {
-            bail!(self.tok.1.error("expecting EOF"));
+            bail!(self.tok.1.syntax_error("expecting EOF"));
         }
         Ok(query)
     }
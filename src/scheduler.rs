@@ -466,6 +466,9 @@ impl Analyzer {
             for import in &m.imports {
                 if let Some(var) = &import.r#as {
                     scope.unscoped.insert(var.source_str());
+                } else if let RefDot { field, .. } = import.refr.as_ref() {
+                    // Without an alias, the import is referred to by its last field.
+                    scope.unscoped.insert(field.0.source_str());
                 }
             }
         }
@@ -1233,3 +1236,254 @@ impl Analyzer {
         Ok(())
     }
 }
+
+// Local variables declared directly within a query via `some` or `:=`. Declarations within
+// comprehensions and `every` belong to nested scopes and are not included.
+fn gather_query_decls(query: &Query, decls: &mut Vec<(Span, &'static str)>) -> Result<()> {
+    let gather = |expr: &Ref<Expr>, kind, decls: &mut Vec<(Span, &'static str)>| {
+        traverse(expr, &mut |e| {
+            if let Var(v) = e.as_ref() {
+                decls.push((v.0.clone(), kind));
+            }
+            Ok(true)
+        })
+    };
+
+    for stmt in &query.stmts {
+        match &stmt.literal {
+            Literal::SomeVars { vars, .. } => {
+                decls.extend(vars.iter().map(|v| (v.clone(), "declared")));
+            }
+            Literal::SomeIn { key, value, .. } => {
+                if let Some(key) = key {
+                    gather(key, "declared", decls)?;
+                }
+                gather(value, "declared", decls)?;
+            }
+            Literal::Expr { expr, .. } => {
+                if let AssignExpr {
+                    op: AssignOp::ColEq,
+                    lhs,
+                    ..
+                } = expr.as_ref()
+                {
+                    gather(lhs, "assigned", decls)?;
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+// Finds local variables that are never used. Like the analyzer, each rule body, comprehension
+// and `every` query is a scope, and a use of a variable refers to the innermost enclosing
+// scope that declares it.
+#[derive(Default)]
+struct UnusedVarChecker {
+    // Declarations of each enclosing scope along with whether they have been used.
+    scopes: Vec<Vec<(Span, &'static str, bool)>>,
+    // All variables occurring in the module.
+    vars: Vec<Span>,
+    unused: Vec<Diagnostic>,
+}
+
+impl UnusedVarChecker {
+    // Check a scope consisting of the given query (if any) and the expressions evaluated
+    // within it. `bound` variables are introduced by the scope but are never reported.
+    fn check_scope(
+        &mut self,
+        query: Option<&Query>,
+        exprs: &[&Ref<Expr>],
+        bound: &[Span],
+    ) -> Result<()> {
+        let mut decls = vec![];
+        if let Some(query) = query {
+            gather_query_decls(query, &mut decls)?;
+        }
+        let mut scope: Vec<_> = decls.into_iter().map(|(d, k)| (d, k, false)).collect();
+        scope.extend(bound.iter().map(|b| (b.clone(), "bound", true)));
+        self.scopes.push(scope);
+
+        for expr in exprs {
+            self.use_expr(expr)?;
+        }
+        if let Some(query) = query {
+            self.use_query(query)?;
+        }
+
+        for (decl, kind, used) in self.scopes.pop().unwrap_or_default() {
+            let name = decl.text();
+            if !used && name != "_" {
+                self.unused.push(Diagnostic {
+                    message: format!("{kind} var `{name}` unused"),
+                    span: decl,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn use_var(&mut self, var: &Span) {
+        self.vars.push(var.clone());
+        let name = var.text();
+        for scope in self.scopes.iter_mut().rev() {
+            if scope.iter().any(|(d, _, _)| d.text() == name) {
+                // The occurrence that declares the variable is not a use.
+                for (d, _, used) in scope.iter_mut() {
+                    if d.text() == name && d.start != var.start {
+                        *used = true;
+                    }
+                }
+                return;
+            }
+        }
+    }
+
+    fn use_expr(&mut self, expr: &Ref<Expr>) -> Result<()> {
+        let mut vars = vec![];
+        let mut comprs = vec![];
+        traverse(expr, &mut |e| {
+            match e.as_ref() {
+                Var(v) => vars.push(v.0.clone()),
+                ArrayCompr { .. } | SetCompr { .. } | ObjectCompr { .. } => comprs.push(e.clone()),
+                _ => (),
+            }
+            Ok(true)
+        })?;
+
+        for v in &vars {
+            self.use_var(v);
+        }
+        for compr in comprs {
+            match compr.as_ref() {
+                ArrayCompr { term, query, .. } | SetCompr { term, query, .. } => {
+                    self.check_scope(Some(query.as_ref()), &[term], &[])?
+                }
+                ObjectCompr {
+                    key, value, query, ..
+                } => self.check_scope(Some(query.as_ref()), &[key, value], &[])?,
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn use_query(&mut self, query: &Query) -> Result<()> {
+        for stmt in &query.stmts {
+            for wm in &stmt.with_mods {
+                self.use_expr(&wm.refr)?;
+                self.use_expr(&wm.r#as)?;
+            }
+            match &stmt.literal {
+                Literal::SomeVars { vars, .. } => {
+                    for v in vars {
+                        self.use_var(v);
+                    }
+                }
+                Literal::SomeIn {
+                    key,
+                    value,
+                    collection,
+                    ..
+                } => {
+                    if let Some(key) = key {
+                        self.use_expr(key)?;
+                    }
+                    self.use_expr(value)?;
+                    self.use_expr(collection)?;
+                }
+                Literal::Expr { expr, .. } | Literal::NotExpr { expr, .. } => {
+                    self.use_expr(expr)?
+                }
+                Literal::Every {
+                    key,
+                    value,
+                    domain,
+                    query,
+                    ..
+                } => {
+                    self.use_expr(domain)?;
+                    let bound: Vec<Span> = key.iter().chain([value]).cloned().collect();
+                    self.check_scope(Some(query.as_ref()), &[], &bound)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_rule(&mut self, rule: &Rule) -> Result<()> {
+        match rule {
+            Rule::Spec { head, bodies, .. } => {
+                let (mut exprs, assign) = match head {
+                    RuleHead::Compr { refr, assign, .. } => (vec![refr], assign),
+                    RuleHead::Set { refr, key, .. } => {
+                        (key.iter().chain([refr]).collect::<Vec<_>>(), &None)
+                    }
+                    RuleHead::Func {
+                        refr, args, assign, ..
+                    } => (args.iter().chain([refr]).collect(), assign),
+                };
+
+                // The head is evaluated within each body. Its value belongs to the first body;
+                // else branches have their own.
+                if bodies.is_empty() {
+                    exprs.extend(assign.iter().map(|a| &a.value));
+                    return self.check_scope(None, &exprs, &[]);
+                }
+                for (idx, body) in bodies.iter().enumerate() {
+                    let mut body_exprs = exprs.clone();
+                    let assign = if idx == 0 { assign } else { &body.assign };
+                    body_exprs.extend(assign.iter().map(|a| &a.value));
+                    self.check_scope(Some(body.query.as_ref()), &body_exprs, &[])?;
+                }
+                Ok(())
+            }
+            Rule::Default { refr, value, .. } => self.check_scope(None, &[refr, value], &[]),
+        }
+    }
+}
+
+/// Perform the additional checks of strict compilation.
+///
+/// Local variables that are declared or assigned but never used, and imports that are never
+/// referenced are reported.
+pub fn check_strict(modules: &[Ref<Module>]) -> Result<Vec<Diagnostic>> {
+    let mut diagnostics = vec![];
+    for module in modules {
+        let mut checker = UnusedVarChecker::default();
+        for rule in &module.policy {
+            checker.check_rule(rule)?;
+        }
+
+        // Nested scopes are checked first. Report in source order.
+        checker.unused.sort_by_key(|d| d.span.start);
+        checker
+            .unused
+            .dedup_by(|a, b| a.span.start == b.span.start && a.message == b.message);
+        diagnostics.append(&mut checker.unused);
+        let module_vars = checker.vars;
+
+        for import in &module.imports {
+            let name = match &import.r#as {
+                Some(s) => s.text(),
+                _ => match import.refr.as_ref() {
+                    RefDot { field, .. } => field.0.text(),
+                    _ => continue,
+                },
+            };
+            // Keyword imports such as rego.v1 and future.keywords are not references.
+            let path = get_path_string(&import.refr, None).unwrap_or_default();
+            if path.starts_with("future.") || path.starts_with("rego.") {
+                continue;
+            }
+            if !module_vars.iter().any(|v| v.text() == name) {
+                diagnostics.push(Diagnostic {
+                    message: format!("import `{}` unused", import.refr.span().text()),
+                    span: import.span.clone(),
+                });
+            }
+        }
+    }
+    Ok(diagnostics)
+}
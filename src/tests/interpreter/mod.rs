@@ -299,6 +299,7 @@ fn yaml_test_impl(file: &str) -> Result<()> {
             "generate.yaml",
             "parse.yaml",
             "tests.yaml",
+            "urlquery.yaml",
         ];
         for s in skip {
             if file.contains(s) {
@@ -386,7 +387,89 @@ fn yaml_test_impl(file: &str) -> Result<()> {
 
 fn yaml_test(file: &str) -> Result<()> {
     #[cfg(not(feature = "rego-extensions"))]
-    if file.contains("rego-extensions") {
+    if file.contains("rego-extensions/") {
+        return Ok(());
+    }
+
+    #[cfg(not(feature = "base32"))]
+    if file.contains("encoding/base32.yaml") {
+        return Ok(());
+    }
+
+    #[cfg(not(feature = "cbor"))]
+    if file.contains("encoding/cbor.yaml") {
+        return Ok(());
+    }
+
+    #[cfg(not(feature = "toml"))]
+    if file.contains("encoding/toml.yaml") {
+        return Ok(());
+    }
+
+    #[cfg(not(feature = "xml"))]
+    if file.contains("encoding/xml.yaml") {
+        return Ok(());
+    }
+
+    #[cfg(not(feature = "z85"))]
+    if file.contains("encoding/z85.yaml") {
+        return Ok(());
+    }
+
+    #[cfg(not(feature = "base64"))]
+    if file.contains("encoding/base64.yaml") {
+        return Ok(());
+    }
+
+    #[cfg(not(feature = "hex"))]
+    if file.contains("encoding/hex.yaml") {
+        return Ok(());
+    }
+
+    #[cfg(not(feature = "jwt"))]
+    if file.contains("jwt/") {
+        return Ok(());
+    }
+
+    #[cfg(not(feature = "crypto"))]
+    if file.contains("crypto/") {
+        return Ok(());
+    }
+
+    #[cfg(not(feature = "x509"))]
+    if file.contains("crypto/x509") {
+        return Ok(());
+    }
+
+    #[cfg(not(feature = "regex"))]
+    if file.contains("regex/") {
+        return Ok(());
+    }
+
+    #[cfg(not(feature = "net"))]
+    if file.contains("net/") {
+        return Ok(());
+    }
+
+    #[cfg(not(feature = "graph"))]
+    if file.contains("graph/") {
+        return Ok(());
+    }
+
+    #[cfg(not(feature = "jsonschema"))]
+    if file.contains("objects/json.match_schema")
+        || file.contains("objects/json.is_valid_with_schema.yaml")
+    {
+        return Ok(());
+    }
+
+    #[cfg(not(feature = "yaml"))]
+    if file.contains("encoding/yaml.yaml") {
+        return Ok(());
+    }
+
+    #[cfg(not(feature = "yaml"))]
+    if file.contains("rego/metadata.yaml") {
         return Ok(());
     }
 
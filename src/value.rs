@@ -27,6 +27,10 @@ use crate::*;
 ///    - [`Value::Number`] has at least 100 digits of precision for computations.
 ///
 /// Value can be efficiently cloned due to the use of reference counting.
+///
+/// Like in OPA, values are ordered first by type: null, boolean, number, string, array,
+/// object and then set. Arrays and sets compare their elements in order and then their
+/// lengths. Objects compare their sorted entries key first, then value, and then their sizes.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Value {
     /// JSON null.
@@ -45,15 +49,15 @@ pub enum Value {
     /// JSON array.
     Array(Rc<Vec<Value>>),
 
-    /// A set of values.
-    /// No JSON equivalent.
-    /// Sets are serialized as arrays in JSON.
-    Set(Rc<BTreeSet<Value>>),
-
     /// An object.
     /// Unlike JSON, keys can be any value, not just string.
     Object(Rc<BTreeMap<Value, Value>>),
 
+    /// A set of values.
+    /// No JSON equivalent.
+    /// Sets are serialized as arrays in JSON, with elements in sorted order.
+    Set(Rc<BTreeSet<Value>>),
+
     /// Undefined value.
     /// Used to indicate the absence of a value.
     Undefined,
@@ -74,19 +78,32 @@ impl Serialize for Value {
             Value::Array(a) => a.serialize(serializer),
             Value::Object(fields) => {
                 let mut map = serializer.serialize_map(Some(fields.len()))?;
-                for (k, v) in fields.iter() {
-                    match k {
-                        Value::String(_) => map.serialize_entry(k, v)?,
-                        _ => {
-                            let key_str = serde_json::to_string(k).map_err(Error::custom)?;
-                            map.serialize_entry(&key_str, v)?
-                        }
+                if fields.keys().all(|k| matches!(k, Value::String(_))) {
+                    // String keys are already in sorted order.
+                    for (k, v) in fields.iter() {
+                        map.serialize_entry(k, v)?;
+                    }
+                } else {
+                    // Non string keys are serialized to strings, which may not sort in the
+                    // same order as the keys themselves, e.g. 10 < 9 as strings.
+                    let mut entries = vec![];
+                    for (k, v) in fields.iter() {
+                        let key_str = match k {
+                            Value::String(s) => s.to_string(),
+                            _ => serde_json::to_string(k).map_err(Error::custom)?,
+                        };
+                        entries.push((key_str, v));
+                    }
+                    entries.sort_by(|a, b| a.0.cmp(&b.0));
+                    for (k, v) in entries {
+                        map.serialize_entry(&k, v)?;
                     }
                 }
                 map.end()
             }
 
-            // display set as an array
+            // display set as an array. BTreeSet iterates in sorted order, so the output is
+            // deterministic and matches the ordering used for comparison.
             Value::Set(s) => s.serialize(serializer),
 
             // display undefined as a special string
@@ -213,6 +230,170 @@ impl<'de> Deserialize<'de> for Value {
     }
 }
 
+/// Limits on the size of documents deserialized into a [`Value`].
+///
+/// Used to reject untrusted documents that are too deeply nested or too large
+/// before they are fully allocated. A limit of `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ValueLimits {
+    /// Maximum nesting depth of arrays, sets and objects. Scalars have depth 0 and
+    /// `[[1]]` has depth 2.
+    pub max_depth: Option<usize>,
+
+    /// Maximum number of values in the document, counting every array, set and object,
+    /// every element and every object key.
+    pub max_nodes: Option<usize>,
+}
+
+impl ValueLimits {
+    fn check_depth<E: de::Error>(&self, depth: usize) -> Result<(), E> {
+        match self.max_depth {
+            Some(max_depth) if depth > max_depth => Err(E::custom(format!(
+                "value exceeds maximum depth of {max_depth}"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_nodes<E: de::Error>(&self, nodes: &core::cell::Cell<usize>) -> Result<(), E> {
+        nodes.set(nodes.get() + 1);
+        match self.max_nodes {
+            Some(max_nodes) if nodes.get() > max_nodes => Err(E::custom(format!(
+                "value exceeds maximum node count of {max_nodes}"
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Deserializes a value while enforcing limits, so that oversized documents are
+// rejected as soon as a limit is crossed.
+struct LimitedValueSeed<'a> {
+    limits: &'a ValueLimits,
+    depth: usize,
+    nodes: &'a core::cell::Cell<usize>,
+}
+
+impl<'a> LimitedValueSeed<'a> {
+    fn child(&self) -> Self {
+        Self {
+            limits: self.limits,
+            depth: self.depth + 1,
+            nodes: self.nodes,
+        }
+    }
+
+    fn scalar<E: de::Error>(&self, value: Value) -> Result<Value, E> {
+        self.limits.check_nodes(self.nodes)?;
+        Ok(value)
+    }
+}
+
+impl<'de, 'a> de::DeserializeSeed<'de> for LimitedValueSeed<'a> {
+    type Value = Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for LimitedValueSeed<'a> {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a value")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.scalar(ValueVisitor.visit_unit()?)
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        self.scalar(ValueVisitor.visit_bool(v)?)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        self.scalar(ValueVisitor.visit_u64(v)?)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        self.scalar(ValueVisitor.visit_i64(v)?)
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<Self::Value, E> {
+        self.scalar(ValueVisitor.visit_u128(v)?)
+    }
+
+    fn visit_i128<E: de::Error>(self, v: i128) -> Result<Self::Value, E> {
+        self.scalar(ValueVisitor.visit_i128(v)?)
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        self.scalar(ValueVisitor.visit_f64(v)?)
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+        self.scalar(ValueVisitor.visit_str(s)?)
+    }
+
+    fn visit_string<E: de::Error>(self, s: String) -> Result<Self::Value, E> {
+        self.scalar(ValueVisitor.visit_string(s)?)
+    }
+
+    fn visit_seq<V>(self, mut visitor: V) -> Result<Self::Value, V::Error>
+    where
+        V: SeqAccess<'de>,
+    {
+        self.limits.check_nodes(self.nodes)?;
+        self.limits.check_depth(self.depth + 1)?;
+        let mut arr = vec![];
+        while let Some(v) = visitor.next_element_seed(self.child())? {
+            arr.push(v);
+        }
+        Ok(Value::from(arr))
+    }
+
+    fn visit_map<V>(self, mut visitor: V) -> Result<Self::Value, V::Error>
+    where
+        V: MapAccess<'de>,
+    {
+        self.limits.check_nodes(self.nodes)?;
+        let mut map = BTreeMap::new();
+        let Some(key) = visitor.next_key_seed(self.child())? else {
+            self.limits.check_depth(self.depth + 1)?;
+            return Ok(Value::from(map));
+        };
+
+        // Big numbers are encoded as single entry maps, so the depth is checked only
+        // once the map is known to be an object.
+        let is_number =
+            matches!(&key, Value::String(k) if k.as_ref() == "$serde_json::private::Number");
+        if !is_number {
+            self.limits.check_depth(self.depth + 1)?;
+        }
+        let value = visitor.next_value_seed(self.child())?;
+        if is_number {
+            if let Value::String(v) = &value {
+                return match Number::from_str(v) {
+                    Ok(n) => Ok(Value::from(n)),
+                    _ => Err(de::Error::custom("failed to read big number")),
+                };
+            }
+            self.limits.check_depth(self.depth + 1)?;
+        }
+        map.insert(key, value);
+
+        while let Some(key) = visitor.next_key_seed(self.child())? {
+            let value = visitor.next_value_seed(self.child())?;
+            map.insert(key, value);
+        }
+        Ok(Value::from(map))
+    }
+}
+
 impl fmt::Display for Value {
     /// Display a value.
     ///
@@ -312,6 +493,93 @@ impl Value {
         serde_json::from_str(json).map_err(anyhow::Error::msg)
     }
 
+    /// Deserialize a [`Value`] from JSON, failing if the document exceeds the given limits.
+    ///
+    /// Parsing stops as soon as a limit is exceeded, so oversized documents are never
+    /// fully allocated.
+    /// ```
+    /// # use regorus::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let limits = ValueLimits {
+    ///     max_depth: Some(2),
+    ///     max_nodes: Some(10),
+    /// };
+    ///
+    /// // Depth 2, 5 nodes.
+    /// let value = Value::from_json_str_with_limits(r#"{ "a": [1, 2] }"#, &limits)?;
+    /// assert_eq!(value, Value::from_json_str(r#"{ "a": [1, 2] }"#)?);
+    ///
+    /// // Depth 3.
+    /// assert!(Value::from_json_str_with_limits("[[[1]]]", &limits).is_err());
+    ///
+    /// // 11 nodes.
+    /// assert!(Value::from_json_str_with_limits("[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]", &limits).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_json_str_with_limits(json: &str, limits: &ValueLimits) -> Result<Value> {
+        let nodes = core::cell::Cell::new(0);
+        let seed = LimitedValueSeed {
+            limits,
+            depth: 0,
+            nodes: &nodes,
+        };
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        let value = de::DeserializeSeed::deserialize(seed, &mut deserializer)
+            .map_err(anyhow::Error::msg)?;
+        deserializer.end().map_err(anyhow::Error::msg)?;
+        Ok(value)
+    }
+
+    /// Check that an existing value does not exceed the given limits.
+    ///
+    /// ```
+    /// # use regorus::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let value = Value::from_json_str(r#"{ "a": [1, 2] }"#)?;
+    ///
+    /// assert!(value.check_limits(&ValueLimits { max_depth: Some(2), max_nodes: Some(5) }).is_ok());
+    /// assert!(value.check_limits(&ValueLimits { max_depth: Some(1), max_nodes: None }).is_err());
+    /// assert!(value.check_limits(&ValueLimits { max_depth: None, max_nodes: Some(4) }).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn check_limits(&self, limits: &ValueLimits) -> Result<()> {
+        fn check(
+            value: &Value,
+            limits: &ValueLimits,
+            depth: usize,
+            nodes: &core::cell::Cell<usize>,
+        ) -> core::result::Result<(), serde_json::Error> {
+            limits.check_nodes(nodes)?;
+            match value {
+                Value::Array(items) => {
+                    limits.check_depth(depth + 1)?;
+                    for item in items.iter() {
+                        check(item, limits, depth + 1, nodes)?;
+                    }
+                }
+                Value::Set(items) => {
+                    limits.check_depth(depth + 1)?;
+                    for item in items.iter() {
+                        check(item, limits, depth + 1, nodes)?;
+                    }
+                }
+                Value::Object(fields) => {
+                    limits.check_depth(depth + 1)?;
+                    for (key, value) in fields.iter() {
+                        check(key, limits, depth + 1, nodes)?;
+                        check(value, limits, depth + 1, nodes)?;
+                    }
+                }
+                _ => (),
+            }
+            Ok(())
+        }
+
+        check(self, limits, 0, &core::cell::Cell::new(0)).map_err(anyhow::Error::msg)
+    }
+
     /// Deserialize a [`Value`] from a file containing JSON.
     ///
     /// ```
@@ -400,6 +668,65 @@ impl Value {
         serde_json::to_string_pretty(self).map_err(anyhow::Error::msg)
     }
 
+    /// Deserialize a [`Value`] from a byte slice containing JSON.
+    ///
+    /// This avoids converting the bytes to a `String` first.
+    ///
+    /// ```
+    /// # use regorus::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let value = Value::from_json_slice(br#"{ "name": "regorus", "tags": [1, 2] }"#)?;
+    ///
+    /// assert_eq!(value["name"], Value::from("regorus"));
+    /// assert_eq!(value["tags"][1], Value::from(2u64));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_json_slice(json: &[u8]) -> Result<Value> {
+        serde_json::from_slice(json).map_err(anyhow::Error::msg)
+    }
+
+    /// Serialize a value to JSON bytes.
+    ///
+    /// The output is identical to that of [`Value::to_json_str`].
+    ///
+    /// ```
+    /// # use regorus::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let value = Value::from_json_str(r#"{ "a": [1, 2] }"#)?;
+    ///
+    /// assert_eq!(value.to_json_vec()?, value.to_json_str()?.into_bytes());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_json_vec(&self) -> Result<Vec<u8>> {
+        serde_json::to_vec_pretty(self).map_err(anyhow::Error::msg)
+    }
+
+    /// Create a [`Value`] from any serializable type.
+    ///
+    /// ```
+    /// # use regorus::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// #[derive(serde::Serialize)]
+    /// struct User {
+    ///     name: String,
+    ///     roles: Vec<String>,
+    /// }
+    ///
+    /// let user = User { name: "alice".to_string(), roles: vec!["admin".to_string()] };
+    /// let value = Value::from_serde(&user)?;
+    ///
+    /// assert_eq!(value["name"], Value::from("alice"));
+    /// assert_eq!(value["roles"][0], Value::from("admin"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_serde<T: Serialize + ?Sized>(value: &T) -> Result<Value> {
+        let json = serde_json::to_value(value).map_err(anyhow::Error::msg)?;
+        Value::deserialize(json).map_err(anyhow::Error::msg)
+    }
+
     /// Deserialize a value from YAML.
     /// Note: Deserialization from YAML does not support arbitrary precision numbers.
     #[cfg(feature = "yaml")]
@@ -420,6 +747,52 @@ impl Value {
             Err(e) => bail!("Failed to read {path}. {e}"),
         }
     }
+
+    /// Deserialize a value from TOML.
+    ///
+    /// Tables become objects and datetimes are converted to their RFC 3339 string form.
+    /// ```
+    /// # use regorus::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let value = Value::from_toml_str(r#"
+    /// title = "example"
+    /// created = 1979-05-27T07:32:00Z
+    ///
+    /// [owner]
+    /// name = "regorus"
+    /// "#)?;
+    ///
+    /// assert_eq!(value["title"], Value::from("example"));
+    /// assert_eq!(value["created"], Value::from("1979-05-27T07:32:00Z"));
+    /// assert_eq!(value["owner"]["name"], Value::from("regorus"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "toml")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "toml")))]
+    pub fn from_toml_str(toml: &str) -> Result<Value> {
+        let table: toml::Table = toml.parse()?;
+        Ok(Self::from_toml_value(toml::Value::Table(table)))
+    }
+
+    #[cfg(feature = "toml")]
+    fn from_toml_value(v: toml::Value) -> Value {
+        match v {
+            toml::Value::String(s) => Value::from(s),
+            toml::Value::Integer(i) => Value::from(i),
+            toml::Value::Float(f) => Value::from(f),
+            toml::Value::Boolean(b) => Value::from(b),
+            toml::Value::Datetime(d) => Value::from(d.to_string()),
+            toml::Value::Array(a) => {
+                Value::from(a.into_iter().map(Self::from_toml_value).collect::<Vec<_>>())
+            }
+            toml::Value::Table(t) => Value::from_map(
+                t.into_iter()
+                    .map(|(k, v)| (Value::from(k), Self::from_toml_value(v)))
+                    .collect(),
+            ),
+        }
+    }
 }
 
 impl From<bool> for Value {
@@ -1119,6 +1492,22 @@ impl Value {
         }
     }
 
+    /// Cast value to [`&str`] if [`Value::String`].
+    /// ```
+    /// # use regorus::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let v = Value::from("Hello");
+    /// assert_eq!(v.as_str()?, "Hello");
+    /// assert!(Value::from(1).as_str().is_err());
+    /// # Ok(())
+    /// # }
+    pub fn as_str(&self) -> Result<&str> {
+        match self {
+            Value::String(s) => Ok(s.as_ref()),
+            _ => Err(anyhow!("not a string")),
+        }
+    }
+
     /// Cast value to [`&mut Rc<str>`] if [`Value::String`].
     /// ```
     /// # use regorus::*;
@@ -1299,6 +1688,73 @@ impl Value {
         }
     }
 
+    /// Get the value at the given path, creating intermediate objects as needed.
+    ///
+    /// Path components index into objects by key and into arrays by position.
+    pub(crate) fn make_or_get_value_at_path_mut<'a>(
+        &'a mut self,
+        path: &[String],
+    ) -> Result<&'a mut Value> {
+        let Some((key, rest)) = path.split_first() else {
+            return Ok(self);
+        };
+        let child = match self {
+            Value::Object(map) => Rc::make_mut(map)
+                .entry(Value::from(key.as_str()))
+                .or_insert_with(|| match rest.is_empty() {
+                    true => Value::Undefined,
+                    false => Value::new_object(),
+                }),
+            Value::Array(a) => {
+                let len = a.len();
+                match key.parse::<usize>() {
+                    Ok(idx) if idx < len => &mut Rc::make_mut(a)[idx],
+                    _ => bail!("`{key}` is not a valid index into array of length {len}"),
+                }
+            }
+            _ => bail!("cannot index `{key}` into non-collection value `{self}`"),
+        };
+        child.make_or_get_value_at_path_mut(rest)
+    }
+
+    /// Remove the value at the given path.
+    ///
+    /// Returns false if there is no value at the path.
+    pub(crate) fn remove_value_at_path(&mut self, path: &[String]) -> Result<bool> {
+        let Some((key, parent_path)) = path.split_last() else {
+            bail!("cannot remove root value");
+        };
+
+        let mut parent = self;
+        for k in parent_path {
+            parent = match parent {
+                Value::Object(map) => match Rc::make_mut(map).get_mut(&Value::from(k.as_str())) {
+                    Some(v) => v,
+                    None => return Ok(false),
+                },
+                Value::Array(a) => match k.parse::<usize>() {
+                    Ok(idx) if idx < a.len() => &mut Rc::make_mut(a)[idx],
+                    _ => return Ok(false),
+                },
+                _ => return Ok(false),
+            };
+        }
+
+        Ok(match parent {
+            Value::Object(map) => Rc::make_mut(map)
+                .remove(&Value::from(key.as_str()))
+                .is_some(),
+            Value::Array(a) => match key.parse::<usize>() {
+                Ok(idx) if idx < a.len() => {
+                    Rc::make_mut(a).remove(idx);
+                    true
+                }
+                _ => false,
+            },
+            _ => false,
+        })
+    }
+
     pub(crate) fn merge(&mut self, mut new: Value) -> Result<()> {
         if self == &new {
             return Ok(());
@@ -1329,6 +1785,39 @@ impl Value {
     }
 }
 
+impl Value {
+    /// Get the value of a key in an object, an element of an array or an item of a set.
+    ///
+    /// Unlike indexing, `None` is returned instead of [`Value::Undefined`] if the key does not
+    /// exist or the value is not a collection.
+    ///
+    /// ```
+    /// # use regorus::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let v = Value::from_json_str(r#"{ "name": "regorus", "tags": ["rego", "opa"] }"#)?;
+    ///
+    /// assert_eq!(v.get("name"), Some(&Value::from("regorus")));
+    /// assert_eq!(v.get("version"), None);
+    ///
+    /// let tags = v.get("tags").expect("missing tags");
+    /// assert_eq!(tags.get(1usize).map(|t| t.as_str()).transpose()?, Some("opa"));
+    /// assert_eq!(tags.get(2usize), None);
+    ///
+    /// assert_eq!(Value::from(1).get(0usize), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get<T>(&self, key: T) -> Option<&Value>
+    where
+        Value: From<T>,
+    {
+        match &self[&Value::from(key)] {
+            Value::Undefined => None,
+            v => Some(v),
+        }
+    }
+}
+
 impl ops::Index<&Value> for Value {
     type Output = Value;
 
@@ -48,6 +48,57 @@ fn extension() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn extension_with_dotted_path() -> Result<()> {
+    let mut engine = Engine::new();
+    engine.add_policy(
+        "test.rego".to_string(),
+        r#"package test
+               import rego.v1
+
+               owner := mycorp.lookup("repo-a")
+               missing := mycorp.lookup("repo-z")
+        "#
+        .to_string(),
+    )?;
+
+    // Extensions must be Send, which Value is only with the arc feature.
+    let owners = std::collections::BTreeMap::from([("repo-a", "alice")]);
+    engine.add_extension(
+        "mycorp.lookup".to_string(),
+        1,
+        Box::new(move |params: Vec<Value>| {
+            let repo = params[0].as_string()?;
+            Ok(owners
+                .get(repo.as_ref())
+                .map_or(Value::Undefined, |owner| Value::from(*owner)))
+        }),
+    )?;
+
+    let r = engine.eval_query("data.test.owner".to_string(), false)?;
+    assert_eq!(r.result[0].expressions[0].value, Value::from("alice"));
+
+    // Undefined results from an extension make the rule undefined.
+    let r = engine.eval_query("data.test.missing".to_string(), false)?;
+    assert!(r.result.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn extension_builtin_conflict() -> Result<()> {
+    let mut engine = Engine::new();
+    for name in ["count", "strings.replace_n", "print"] {
+        let r = engine.add_extension(name.to_string(), 1, Box::new(|_| Ok(Value::Undefined)));
+        assert!(r
+            .unwrap_err()
+            .to_string()
+            .contains("conflicts with builtin"));
+    }
+
+    Ok(())
+}
+
 #[test]
 fn extension_with_state() -> Result<()> {
     #[derive(Clone)]
@@ -101,3 +152,1249 @@ fn extension_with_state() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+#[cfg(feature = "time")]
+fn clock() -> Result<()> {
+    let mut engine = Engine::new();
+    engine.add_policy(
+        "test.rego".to_string(),
+        r#"package test
+               now := time.now_ns()
+               same := time.now_ns() == now
+             "#
+        .to_string(),
+    )?;
+
+    let wall_clock_ns = || -> Result<i64> {
+        Ok(std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_nanos() as i64)
+    };
+
+    // By default, time.now_ns reads the wall-clock once per evaluation.
+    let before = wall_clock_ns()?;
+    let r = engine.eval_query("[data.test.now, data.test.same]".to_string(), false)?;
+    let after = wall_clock_ns()?;
+    let now = r.result[0].expressions[0].value[0].as_i64()?;
+    assert!(before <= now && now <= after);
+    assert_eq!(r.result[0].expressions[0].value[1], Value::from(true));
+
+    // Subsequent evaluations read the clock again.
+    std::thread::sleep(std::time::Duration::from_millis(1));
+    let later = engine.eval_rule("data.test.now".to_string())?.as_i64()?;
+    assert!(later > now);
+
+    // Pinned clock.
+    engine.set_clock(Some(1_000_000_000));
+    for _ in 0..2 {
+        let r = engine.eval_query("[data.test.now, data.test.same]".to_string(), false)?;
+        assert_eq!(
+            r.result[0].expressions[0].value,
+            Value::from(vec![Value::from(1_000_000_000i64), Value::from(true)])
+        );
+    }
+    assert_eq!(
+        engine.eval_rule("data.test.now".to_string())?,
+        Value::from(1_000_000_000i64)
+    );
+
+    // Unpinned clock.
+    engine.set_clock(None);
+    assert!(engine.eval_rule("data.test.now".to_string())?.as_i64()? > later);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "net")]
+fn resolver() -> Result<()> {
+    let mut engine = Engine::new();
+    engine.add_policy(
+        "test.rego".to_string(),
+        r#"package test
+               addrs := net.lookup_ip_addr(input.host)
+               internal { net.cidr_contains("10.0.0.0/8", net.lookup_ip_addr(input.host)[_]) }
+             "#
+        .to_string(),
+    )?;
+    engine.set_input(Value::from_json_str(r#"{"host": "db.example.com"}"#)?);
+
+    // Without a resolver, lookups are errors.
+    let r = engine.eval_rule("data.test.addrs".to_string());
+    assert!(r
+        .unwrap_err()
+        .to_string()
+        .contains("`net.lookup_ip_addr` requires a resolver"));
+
+    let lookups = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = lookups.clone();
+    engine.set_resolver(Some(Box::new(
+        move |name: &str| -> Result<Vec<std::net::IpAddr>> {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            match name {
+                "db.example.com" => Ok(vec!["10.1.2.3".parse()?, "2001:db8::1".parse()?]),
+                _ => bail!("no such host"),
+            }
+        },
+    )));
+
+    let mut expected = Value::new_set();
+    expected.as_set_mut()?.insert(Value::from("10.1.2.3"));
+    expected.as_set_mut()?.insert(Value::from("2001:db8::1"));
+    assert_eq!(engine.eval_rule("data.test.addrs".to_string())?, expected);
+
+    // Lookups are cached for the duration of an evaluation.
+    let before = lookups.load(std::sync::atomic::Ordering::SeqCst);
+    let r = engine.eval_query("[data.test.addrs, data.test.internal]".to_string(), false)?;
+    assert_eq!(r.result[0].expressions[0].value[0], expected);
+    assert_eq!(r.result[0].expressions[0].value[1], Value::from(true));
+    assert_eq!(
+        lookups.load(std::sync::atomic::Ordering::SeqCst),
+        before + 1
+    );
+
+    // Resolver errors are reported.
+    engine.set_input(Value::from_json_str(r#"{"host": "unknown.example.com"}"#)?);
+    let r = engine.eval_rule("data.test.addrs".to_string());
+    assert!(r.unwrap_err().to_string().contains("no such host"));
+
+    // Removing the resolver.
+    engine.set_resolver(None);
+    engine.set_input(Value::from_json_str(r#"{"host": "db.example.com"}"#)?);
+    assert!(engine.eval_rule("data.test.addrs".to_string()).is_err());
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn rand_seed() -> Result<()> {
+    let mut engine = Engine::new();
+    engine.add_policy(
+        "test.rego".to_string(),
+        r#"package test
+               values := [rand.intn("a", 1000000), rand.intn("b", 1000000), rand.intn("a", 1000000)]
+             "#
+        .to_string(),
+    )?;
+
+    // The same key yields the same value within an evaluation.
+    let values = engine.eval_rule("data.test.values".to_string())?;
+    assert_eq!(values[0], values[2]);
+
+    // Seeded evaluations are reproducible, including across engines.
+    engine.set_rand_seed(Some(7));
+    let seeded = engine.eval_rule("data.test.values".to_string())?;
+    assert_eq!(engine.eval_rule("data.test.values".to_string())?, seeded);
+
+    let mut engine2 = engine.clone();
+    assert_eq!(engine2.eval_rule("data.test.values".to_string())?, seeded);
+
+    engine2.set_rand_seed(Some(8));
+    assert_ne!(engine2.eval_rule("data.test.values".to_string())?, seeded);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(all(feature = "std", feature = "uuid", feature = "time"))]
+fn deterministic_evaluation() -> Result<()> {
+    let policy = r#"package test
+               decision := {
+                 "now": time.now_ns(),
+                 "n": rand.intn("n", 1000000),
+                 "id": uuid.rfc4122("request"),
+                 "same_id": uuid.rfc4122("request") == uuid.rfc4122("request"),
+                 "other_id": uuid.rfc4122("other"),
+               }
+             "#;
+
+    let new_engine = || -> Result<Engine> {
+        let mut engine = Engine::new();
+        engine.add_policy("test.rego".to_string(), policy.to_string())?;
+        engine.set_clock(Some(1_700_000_000_000_000_000));
+        engine.set_rand_seed(Some(42));
+        Ok(engine)
+    };
+
+    let mut engine = new_engine()?;
+    let decision = engine.eval_rule("data.test.decision".to_string())?;
+    assert_eq!(decision["now"], Value::from(1_700_000_000_000_000_000_i64));
+    assert_eq!(decision["same_id"], Value::from(true));
+    assert_ne!(decision["id"], decision["other_id"]);
+
+    // Same engine, same decision.
+    assert_eq!(
+        engine.eval_rule("data.test.decision".to_string())?,
+        decision
+    );
+
+    // Independent engine with the same clock and seed, same decision.
+    let mut engine2 = new_engine()?;
+    assert_eq!(
+        engine2.eval_rule("data.test.decision".to_string())?,
+        decision
+    );
+
+    // A different seed yields different random values.
+    engine2.set_rand_seed(Some(43));
+    let decision2 = engine2.eval_rule("data.test.decision".to_string())?;
+    assert_eq!(decision2["now"], decision["now"]);
+    assert_ne!(decision2["id"], decision["id"]);
+
+    // Without a seed, uuids are unpredictable.
+    engine2.set_rand_seed(None);
+    let d1 = engine2.eval_rule("data.test.decision".to_string())?;
+    let d2 = engine2.eval_rule("data.test.decision".to_string())?;
+    assert_ne!(d1["id"], d2["id"]);
+
+    Ok(())
+}
+
+#[test]
+fn eval_bool_query() -> Result<()> {
+    let mut engine = Engine::new();
+    engine.add_policy(
+        "test.rego".to_string(),
+        r#"package test
+               import rego.v1
+
+               default allow := false
+               allow if input.user == "admin"
+
+               deny if input.user == "guest"
+
+               role := "admin"
+        "#
+        .to_string(),
+    )?;
+
+    let mut eval = |input: &str, query: &str| {
+        engine.set_input(Value::from_json_str(input)?);
+        engine.eval_bool_query(query.to_string(), false)
+    };
+
+    // True and false rules.
+    assert!(eval(r#"{"user": "admin"}"#, "data.test.allow")?);
+    assert!(!eval(r#"{"user": "bob"}"#, "data.test.allow")?);
+
+    // Undefined rule.
+    let err = eval(r#"{"user": "bob"}"#, "data.test.deny").unwrap_err();
+    assert_eq!(err.to_string(), "query is undefined");
+
+    // Non-boolean rule.
+    let err = eval(r#"{"user": "bob"}"#, "data.test.role").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "query produced non-boolean value `\"admin\"`"
+    );
+
+    // Multiple results.
+    let err = eval(r#"{"user": "bob"}"#, "x := [true, false][_]").unwrap_err();
+    assert_eq!(err.to_string(), "query produced multiple results");
+
+    Ok(())
+}
+
+#[test]
+fn eval_rule() -> Result<()> {
+    let mut engine = Engine::new();
+    engine.add_policy(
+        "authz.rego".to_string(),
+        r#"package authz
+               import rego.v1
+
+               allow if input.user in admins
+
+               admins contains "alice"
+               admins contains "bob" if input.allow_bob
+
+               roles[user] := "admin" if some user in admins
+        "#
+        .to_string(),
+    )?;
+    engine.add_policy(
+        "authz_extra.rego".to_string(),
+        r#"package authz
+               import rego.v1
+
+               admins contains "carol"
+        "#
+        .to_string(),
+    )?;
+    engine.set_input(Value::from_json_str(r#"{"user": "carol"}"#)?);
+
+    // Complete rule.
+    assert_eq!(
+        engine.eval_rule("data.authz.allow".to_string())?,
+        Value::from(true)
+    );
+
+    // Partial set rule aggregated across definitions and files.
+    assert_eq!(
+        engine.eval_rule("data.authz.admins".to_string())?,
+        Value::from(std::collections::BTreeSet::from([
+            Value::from("alice"),
+            Value::from("carol")
+        ]))
+    );
+
+    // Partial object rule.
+    assert_eq!(
+        engine.eval_rule("data.authz.roles".to_string())?,
+        Value::from_json_str(r#"{"alice": "admin", "carol": "admin"}"#)?
+    );
+
+    // Undefined complete rule.
+    engine.set_input(Value::from_json_str(r#"{"user": "dave"}"#)?);
+    assert_eq!(
+        engine.eval_rule("data.authz.allow".to_string())?,
+        Value::Undefined
+    );
+
+    // Unknown path.
+    let err = engine.eval_rule("data.authz.deny".to_string()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`data.authz.deny` is not a valid rule path"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn prepared_engine_reuse() -> Result<()> {
+    let mut engine = Engine::new();
+    engine.add_policy(
+        "test.rego".to_string(),
+        r#"package test
+               import rego.v1
+
+               allow if input.user in data.admins
+
+               greeting := sprintf("hello %s", [input.user])
+        "#
+        .to_string(),
+    )?;
+    engine.add_data(Value::from_json_str(r#"{"admins": ["alice"]}"#)?)?;
+
+    let mut prepared = engine.clone();
+    prepared.prepare()?;
+
+    for user in ["alice", "bob", "alice"] {
+        let input = Value::from_json_str(&format!(r#"{{"user": "{user}"}}"#))?;
+
+        // Fresh engine that prepares as part of evaluation.
+        let mut fresh = engine.clone();
+        fresh.set_input(input.clone());
+
+        // Clone of the engine that has already been prepared.
+        let mut reused = prepared.clone();
+        reused.set_input(input);
+
+        for rule in ["data.test.allow", "data.test.greeting"] {
+            assert_eq!(
+                reused.eval_rule(rule.to_string())?,
+                fresh.eval_rule(rule.to_string())?
+            );
+        }
+        assert_eq!(
+            reused.eval_query("data.test".to_string(), false)?,
+            fresh.eval_query("data.test".to_string(), false)?
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn patch_and_remove_data() -> Result<()> {
+    let mut engine = Engine::new();
+    engine.add_policy(
+        "test.rego".to_string(),
+        r#"package test
+               import rego.v1
+
+               cpu_limit := data.limits.cpu
+               memory_limit := data.limits.memory
+               admins := {name | some name, user in data.users; user.admin}
+        "#
+        .to_string(),
+    )?;
+    engine.add_data(Value::from_json_str(
+        r#"{
+             "limits": { "cpu": 2, "memory": 4 },
+             "users": { "alice": { "admin": true }, "bob": { "admin": false } }
+           }"#,
+    )?)?;
+    engine.prepare()?;
+
+    let eval = |engine: &mut Engine, rule: &str| engine.eval_rule(format!("data.test.{rule}"));
+    assert_eq!(eval(&mut engine, "cpu_limit")?, Value::from(2));
+
+    // Patch a nested key. The change is visible and unrelated values are unchanged.
+    // The compiled policies are reused.
+    engine.patch_data("/limits/cpu", Value::from(8))?;
+    assert!(engine.is_prepared());
+    assert_eq!(eval(&mut engine, "cpu_limit")?, Value::from(8));
+    assert_eq!(eval(&mut engine, "memory_limit")?, Value::from(4));
+    assert_eq!(
+        eval(&mut engine, "admins")?,
+        Value::from(std::collections::BTreeSet::from([Value::from("alice")]))
+    );
+
+    // Add a new nested object, creating intermediate objects.
+    engine.patch_data("/users/carol/admin", Value::from(true))?;
+    let admins = eval(&mut engine, "admins")?;
+    assert!(admins.as_set()?.contains(&Value::from("carol")));
+
+    // Clones of the engine are unaffected by subsequent patches.
+    let mut snapshot = engine.clone();
+    engine.remove_data("/users/alice")?;
+    assert!(!eval(&mut engine, "admins")?
+        .as_set()?
+        .contains(&Value::from("alice")));
+    assert!(eval(&mut snapshot, "admins")?
+        .as_set()?
+        .contains(&Value::from("alice")));
+
+    // Removing a missing path is not an error.
+    assert!(!engine.remove_data("/users/alice")?);
+    assert!(!engine.remove_data("/nothing/here")?);
+
+    // Removing makes rules depending on the value undefined.
+    assert!(engine.remove_data("/limits/memory")?);
+    assert_eq!(eval(&mut engine, "memory_limit")?, Value::Undefined);
+
+    // JSON pointer escapes.
+    engine.patch_data("/paths/a~1b~0c", Value::from(1))?;
+    assert_eq!(engine.get_data()["paths"]["a/b~c"], Value::from(1));
+
+    // Array elements are addressed by index.
+    engine.patch_data("/list", Value::from_json_str("[1, 2, 3]")?)?;
+    engine.patch_data("/list/1", Value::from(20))?;
+    assert!(engine.remove_data("/list/0")?);
+    assert_eq!(engine.get_data()["list"], Value::from_json_str("[20, 3]")?);
+    assert!(engine.patch_data("/list/5", Value::from(1)).is_err());
+
+    // The whole document can be replaced, but only by an object.
+    assert!(engine.patch_data("", Value::from(1)).is_err());
+    assert!(engine.remove_data("").is_err());
+    engine.patch_data("", Value::from_json_str(r#"{"limits": {"cpu": 1}}"#)?)?;
+    assert_eq!(eval(&mut engine, "cpu_limit")?, Value::from(1));
+    assert!(engine.is_prepared());
+
+    // Changing policies requires them to be compiled again.
+    engine.add_policy(
+        "extra.rego".to_string(),
+        "package extra\nx := 1".to_string(),
+    )?;
+    assert!(!engine.is_prepared());
+
+    Ok(())
+}
+
+#[test]
+fn print_hook() -> Result<()> {
+    use std::sync::{Arc, Mutex};
+
+    let mut engine = Engine::new();
+    engine.add_policy(
+        "test.rego".to_string(),
+        r#"package test
+               import rego.v1
+
+               allow if {
+                 print("user:", input.user, "roles:", input.roles)
+                 input.user == "admin"
+               }
+        "#
+        .to_string(),
+    )?;
+
+    let output = Arc::new(Mutex::new(vec![]));
+    let sink = output.clone();
+    engine.set_print_hook(Some(Box::new(move |msg: &str| {
+        sink.lock().unwrap().push(msg.to_string());
+    })));
+
+    engine.set_input(Value::from_json_str(
+        r#"{"user": "admin", "roles": ["a"]}"#,
+    )?);
+    assert_eq!(
+        engine.eval_rule("data.test.allow".to_string())?,
+        Value::from(true)
+    );
+
+    // An undefined argument suppresses the print but does not make the rule undefined.
+    engine.set_input(Value::from_json_str(r#"{"user": "admin"}"#)?);
+    assert_eq!(
+        engine.eval_rule("data.test.allow".to_string())?,
+        Value::from(true)
+    );
+
+    assert_eq!(
+        *output.lock().unwrap(),
+        vec![r#"test.rego:5: user: admin roles: ["a"]"#]
+    );
+
+    // Gathering takes precedence over the hook.
+    engine.set_input(Value::from_json_str(
+        r#"{"user": "admin", "roles": ["a"]}"#,
+    )?);
+    engine.set_gather_prints(true);
+    engine.eval_rule("data.test.allow".to_string())?;
+    assert_eq!(engine.take_prints()?.len(), 1);
+    assert_eq!(output.lock().unwrap().len(), 1);
+
+    // Removing the hook restores printing to stderr.
+    engine.set_gather_prints(false);
+    engine.set_print_hook(None);
+    engine.eval_rule("data.test.allow".to_string())?;
+    assert_eq!(output.lock().unwrap().len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn explanation() -> Result<()> {
+    let mut engine = Engine::new();
+    engine.add_policy(
+        "authz.rego".to_string(),
+        r#"package authz
+import rego.v1
+
+is_admin(user) if user in data.admins
+
+deny if {
+  user := input.user
+  not is_admin(user)
+  trace(sprintf("%s is not an admin", [user]))
+}
+"#
+        .to_string(),
+    )?;
+    engine.add_data(Value::from_json_str(r#"{"admins": ["alice"]}"#)?)?;
+    engine.set_input(Value::from_json_str(r#"{"user": "bob"}"#)?);
+
+    // Nothing is recorded unless enabled.
+    assert!(engine.take_explanation().is_err());
+    engine.eval_rule("data.authz.deny".to_string())?;
+
+    engine.set_enable_explanation(true);
+    assert_eq!(
+        engine.eval_rule("data.authz.deny".to_string())?,
+        Value::from(true)
+    );
+    let events = engine.take_explanation()?;
+
+    let find = |op: TraceOp, message: &str| {
+        events
+            .iter()
+            .find(|e| e.op == op && e.message == message)
+            .unwrap_or_else(|| panic!("no {op:?} event `{message}` in {events:#?}"))
+    };
+
+    // Rule and function entry.
+    let e = find(TraceOp::Enter, "data.authz.deny");
+    assert_eq!((e.file.as_str(), e.location.row), ("authz.rego", 6));
+    assert_eq!(find(TraceOp::Enter, "data.authz.is_admin").location.row, 4);
+
+    // Statement evaluation and variable binding.
+    assert_eq!(find(TraceOp::Eval, "user := input.user").location.row, 7);
+    let e = find(TraceOp::Bind, r#"user = "bob""#);
+    assert_eq!((e.location.row, e.location.col), (7, 3));
+
+    // Trace note.
+    assert_eq!(find(TraceOp::Note, "bob is not an admin").location.row, 9);
+
+    // Events are ordered.
+    let pos = |op, message: &str| events.iter().position(|e| e == find(op, message));
+    assert!(pos(TraceOp::Enter, "data.authz.deny") < pos(TraceOp::Note, "bob is not an admin"));
+
+    // Taking clears the events. Disabling stops recording.
+    assert!(engine.take_explanation()?.is_empty());
+    engine.set_enable_explanation(false);
+    assert!(engine.take_explanation().is_err());
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "opa-runtime")]
+fn opa_runtime() -> Result<()> {
+    let mut engine = Engine::new();
+    engine.add_policy(
+        "test.rego".to_string(),
+        r#"package test
+               import rego.v1
+
+               runtime := opa.runtime()
+               debug if opa.runtime().config.debug
+        "#
+        .to_string(),
+    )?;
+
+    // By default, no environment or config is exposed.
+    let runtime = engine.eval_rule("data.test.runtime".to_string())?;
+    assert_eq!(runtime["env"], Value::new_object());
+    assert_eq!(runtime["config"], Value::new_object());
+    assert!(runtime["regorus-version"].as_string().is_ok());
+
+    // The injected runtime object is returned as is.
+    let injected = Value::from_json_str(
+        r#"{
+             "env": { "HOME": "/home/app", "REGION": "westus" },
+             "config": { "debug": true, "labels": { "tier": "prod" } }
+           }"#,
+    )?;
+    engine.set_runtime(Some(injected.clone()))?;
+    assert_eq!(engine.eval_rule("data.test.runtime".to_string())?, injected);
+    assert_eq!(
+        engine.eval_rule("data.test.debug".to_string())?,
+        Value::from(true)
+    );
+
+    // Clones share the runtime.
+    let mut engine2 = engine.clone();
+    assert_eq!(
+        engine2.eval_rule("data.test.runtime".to_string())?,
+        injected
+    );
+
+    // Restore the default.
+    engine.set_runtime(None)?;
+    assert_eq!(
+        engine.eval_rule("data.test.runtime".to_string())?["env"],
+        Value::new_object()
+    );
+
+    assert!(engine.set_runtime(Some(Value::from("env"))).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn partial_eval() -> Result<()> {
+    let mut engine = Engine::new();
+    engine.add_policy(
+        "test.rego".to_string(),
+        r#"package test
+               import rego.v1
+
+               threshold := 5
+               roles := {"admin", "owner"}
+
+               allow if {
+                 input.user.role in roles
+                 input.amount <= threshold * 100
+               }
+
+               allow if input.user.name == data.owner
+
+               deny if not input.user.active
+        "#
+        .to_string(),
+    )?;
+    engine.add_data(Value::from_json_str(r#"{ "owner": "alice" }"#)?)?;
+
+    let unknowns = || vec!["input.x".to_string()];
+    let empty: Vec<String> = vec![];
+
+    // Simple comparison against an unknown.
+    let result = engine.partial_eval("input.x > 5".to_string(), unknowns())?;
+    assert_eq!(result.queries, [["input.x > 5"]]);
+
+    // Fully known queries collapse to a constant.
+    let result = engine.partial_eval("1 + 1 == 2".to_string(), unknowns())?;
+    assert_eq!(result.queries, [empty.clone()]);
+    let result = engine.partial_eval("data.test.threshold > 10".to_string(), unknowns())?;
+    assert!(result.queries.is_empty());
+
+    // Known terms of a conjunction are evaluated and substituted.
+    let result = engine.partial_eval(
+        "y := data.test.threshold * 2; input.y == 1; input.x > y; input.x != y + 1".to_string(),
+        vec!["input.x".to_string(), "input.y".to_string()],
+    )?;
+    assert_eq!(
+        result.queries,
+        [["input.y == 1", "input.x > 10", "input.x != 11"]]
+    );
+
+    // A false known term makes the whole conjunction false.
+    let result = engine.partial_eval("input.x > 5; 1 > 2".to_string(), unknowns())?;
+    assert!(result.queries.is_empty());
+
+    // Variables bound to unknowns are replaced by their residuals.
+    let result = engine.partial_eval("z := input.x.y; count(z) > 1".to_string(), unknowns())?;
+    assert_eq!(result.queries, [["count(input.x.y) > 1"]]);
+
+    // Rules depending on unknowns are inlined; each definition yields a residual query.
+    let result = engine.partial_eval("data.test.allow".to_string(), vec!["input".to_string()])?;
+    assert_eq!(
+        result.queries,
+        [
+            vec![
+                r#"input.user.role in {"admin", "owner"}"#,
+                "input.amount <= 500"
+            ],
+            vec![r#"input.user.name == "alice""#]
+        ]
+    );
+
+    let result = engine.partial_eval("data.test.deny".to_string(), vec!["input".to_string()])?;
+    assert_eq!(result.queries, [["not input.user.active"]]);
+
+    // Unknowns that the query does not touch leave it fully evaluated.
+    let result = engine.partial_eval("data.test.allow".to_string(), vec!["input.z".to_string()])?;
+    assert!(result.queries.is_empty());
+
+    // Unsupported constructs are reported.
+    assert!(engine
+        .partial_eval("some v in input.x; v > 1".to_string(), unknowns())
+        .is_err());
+    assert!(engine
+        .partial_eval("input.x > 1".to_string(), vec!["x".to_string()])
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn strict_mode_scopes() -> Result<()> {
+    let mut engine = Engine::new();
+    engine.add_policy(
+        "scopes.rego".to_string(),
+        r#"package scopes
+               import rego.v1
+
+               p := 1 if {
+                 y := input.y
+               } else := y if {
+                 y := 2
+               }
+        "#
+        .to_string(),
+    )?;
+
+    // Each body has its own scope. A variable is not considered used because another
+    // variable of the same name is used in a different body.
+    let diagnostics = engine.get_strict_diagnostics()?;
+    let found: Vec<(&str, u32, u32)> = diagnostics
+        .iter()
+        .map(|d| (d.message.as_str(), d.span.line, d.span.col))
+        .collect();
+    assert_eq!(found, [("assigned var `y` unused", 5, 18)]);
+
+    Ok(())
+}
+
+#[test]
+fn strict_mode() -> Result<()> {
+    let mut engine = Engine::new();
+    engine.add_policy(
+        "imports.rego".to_string(),
+        r#"package imports
+               import rego.v1
+               import data.roles
+               import data.users as people
+               import input.request as req
+
+               allow if req.user == people.admin
+        "#
+        .to_string(),
+    )?;
+    engine.add_policy(
+        "vars.rego".to_string(),
+        r#"package vars
+               import rego.v1
+
+               allow if {
+                 x := input.x
+                 y := x + 1
+                 some z
+                 [a, _] := [1, 2]
+                 count([v | some v in input.values; w := v]) > 0
+                 a > 0
+               }
+        "#
+        .to_string(),
+    )?;
+
+    // Not strict by default.
+    engine.eval_query("data".to_string(), false)?;
+
+    let diagnostics = engine.get_strict_diagnostics()?;
+    let found: Vec<(&str, &str, u32, u32)> = diagnostics
+        .iter()
+        .map(|d| {
+            (
+                d.span.source.file().as_str(),
+                d.message.as_str(),
+                d.span.line,
+                d.span.col,
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("imports.rego", "import `data.roles` unused", 3, 16),
+            ("vars.rego", "assigned var `y` unused", 6, 18),
+            ("vars.rego", "declared var `z` unused", 7, 23),
+            ("vars.rego", "assigned var `w` unused", 9, 53),
+        ]
+    );
+    assert!(diagnostics[0]
+        .to_string()
+        .contains("error: import `data.roles` unused"));
+
+    engine.set_strict(true);
+    let err = engine
+        .eval_query("data".to_string(), false)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("import `data.roles` unused"));
+    assert!(err.contains("assigned var `y` unused"));
+
+    engine.set_strict(false);
+    assert!(engine.eval_query("data".to_string(), false).is_ok());
+
+    // Unsafe variables are errors irrespective of strict mode.
+    let mut engine = Engine::new();
+    engine.add_policy(
+        "unsafe.rego".to_string(),
+        r#"package unsafe
+               import rego.v1
+
+               allow if x > 1
+        "#
+        .to_string(),
+    )?;
+    let err = engine.prepare().unwrap_err().to_string();
+    assert!(err.contains("use of undefined variable `x` is unsafe"));
+
+    Ok(())
+}
+
+#[test]
+fn module_resolver() -> Result<()> {
+    let policies = |package: &str| -> Result<Option<(String, String)>> {
+        Ok(match package {
+            "data.lib.users" => Some((
+                "users.rego".to_string(),
+                r#"package lib.users
+                   import rego.v1
+                   import data.lib.roles
+
+                   is_admin(u) if data.lib.roles.admins[u]
+                "#
+                .to_string(),
+            )),
+            "data.lib.roles" => Some((
+                "roles.rego".to_string(),
+                r#"package lib.roles
+                   admins := {"alice": true}
+                "#
+                .to_string(),
+            )),
+            "data.cycle.a" => Some((
+                "a.rego".to_string(),
+                "package cycle.a\n import data.cycle.b\n x := data.cycle.b.y".to_string(),
+            )),
+            "data.cycle.b" => Some((
+                "b.rego".to_string(),
+                "package cycle.b\n import data.cycle.a.x\n y := 1".to_string(),
+            )),
+            "data.broken" => Some((
+                "broken.rego".to_string(),
+                "package broken\n x := ".to_string(),
+            )),
+            "data.failing" => bail!("resolver failure"),
+            _ => None,
+        })
+    };
+
+    let mut engine = Engine::new();
+    engine.set_module_resolver(Some(Box::new(policies)));
+    engine.add_policy(
+        "test.rego".to_string(),
+        r#"package test
+               import rego.v1
+               import data.lib.users
+               import data.unknown
+
+               allow if data.lib.users.is_admin(input.user)
+        "#
+        .to_string(),
+    )?;
+
+    // Dependencies are loaded before the importing module.
+    assert_eq!(
+        engine.get_packages()?,
+        ["data.lib.roles", "data.lib.users", "data.test"]
+    );
+
+    engine.set_input(Value::from_json_str(r#"{"user": "alice"}"#)?);
+    assert_eq!(
+        engine.eval_rule("data.test.allow".to_string())?,
+        Value::from(true)
+    );
+
+    // Loaded packages are not resolved again.
+    engine.add_policy(
+        "test2.rego".to_string(),
+        "package test2\n import data.lib.roles\n n := count(data.lib.roles.admins)".to_string(),
+    )?;
+    assert_eq!(engine.get_packages()?.len(), 4);
+
+    // Circular imports are reported.
+    let mut engine = Engine::new();
+    engine.set_module_resolver(Some(Box::new(policies)));
+    let err = engine
+        .add_policy(
+            "test.rego".to_string(),
+            "package test\n import data.cycle.a\n z := data.cycle.a.x".to_string(),
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("circular import: data.cycle.a -> data.cycle.b -> data.cycle.a"),
+        "{err}"
+    );
+    assert!(err.contains("b.rego:2:2"), "{err}");
+
+    // A module importing itself via the resolver is also circular.
+    let err = engine
+        .add_policy(
+            "a.rego".to_string(),
+            "package cycle.a\n import data.cycle.b\n x := data.cycle.b.y".to_string(),
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("circular import: data.cycle.a -> data.cycle.b -> data.cycle.a"));
+
+    // When a later import fails, the dependencies resolved before it are not kept.
+    for failing in ["data.broken", "data.failing"] {
+        let mut engine = Engine::new();
+        engine.set_module_resolver(Some(Box::new(policies)));
+        let policy = format!("package test\n import data.lib.users\n import {failing}\n");
+        assert!(engine.add_policy("test.rego".to_string(), policy).is_err());
+        assert!(engine.get_packages()?.is_empty());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn eval_query_as() -> Result<()> {
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Port {
+        number: u16,
+        protocol: String,
+    }
+
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Limits {
+        cpu: f64,
+        memory: Option<String>,
+    }
+
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Config {
+        name: String,
+        replicas: u32,
+        ports: Vec<Port>,
+        limits: Limits,
+        tags: Vec<String>,
+    }
+
+    let mut engine = Engine::new();
+    engine.add_policy(
+        "test.rego".to_string(),
+        r#"package test
+               import rego.v1
+
+               config := {
+                 "name": input.name,
+                 "replicas": count(input.ports) * 2,
+                 "ports": [{"number": p, "protocol": "tcp"} | some p in input.ports],
+                 "limits": {"cpu": 0.5},
+                 "tags": tags,
+               }
+
+               tags contains "web"
+               tags contains "public" if input.public
+        "#
+        .to_string(),
+    )?;
+    engine.set_input(Value::from_json_str(
+        r#"{ "name": "app", "ports": [80, 443], "public": true }"#,
+    )?);
+
+    let config: Config = engine.eval_query_as("data.test.config".to_string())?;
+    assert_eq!(
+        config,
+        Config {
+            name: "app".to_string(),
+            replicas: 4,
+            ports: vec![
+                Port {
+                    number: 80,
+                    protocol: "tcp".to_string()
+                },
+                Port {
+                    number: 443,
+                    protocol: "tcp".to_string()
+                },
+            ],
+            limits: Limits {
+                cpu: 0.5,
+                memory: None
+            },
+            // Sets are deserialized as sequences.
+            tags: vec!["public".to_string(), "web".to_string()],
+        }
+    );
+
+    let tags: std::collections::BTreeSet<String> =
+        engine.eval_query_as("data.test.tags".to_string())?;
+    assert_eq!(tags.len(), 2);
+
+    // Shape mismatch.
+    let err = engine
+        .eval_query_as::<Vec<Port>>("data.test.config".to_string())
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("query result does not match"), "{err}");
+    assert!(
+        err.contains("invalid type: map, expected a sequence"),
+        "{err}"
+    );
+
+    // Undefined and multiple results.
+    let err = engine.eval_query_as::<bool>("data.test.missing".to_string());
+    assert_eq!(err.unwrap_err().to_string(), "query is undefined");
+    let err = engine.eval_query_as::<u32>("x := [1, 2][_]".to_string());
+    assert_eq!(
+        err.unwrap_err().to_string(),
+        "query produced multiple results"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn value_limits() -> Result<()> {
+    let mut engine = Engine::new();
+    engine.add_policy(
+        "test.rego".to_string(),
+        r#"
+        package test
+        len := count(input.a)
+        "#
+        .to_string(),
+    )?;
+
+    engine.set_value_limits(ValueLimits {
+        max_depth: Some(64),
+        max_nodes: Some(10_000),
+    });
+
+    // Within limits.
+    engine.set_input_json(r#"{ "a": [1, 2, 3] }"#)?;
+    assert_eq!(
+        engine.eval_rule("data.test.len".to_string())?,
+        Value::from(3u64)
+    );
+
+    // Deeply nested document.
+    let deep = format!("{}{}", "[".repeat(65), "]".repeat(65));
+    let err = engine.set_input_json(&deep).unwrap_err().to_string();
+    assert!(err.contains("value exceeds maximum depth of 64"), "{err}");
+    let deep_object = format!(
+        r#"{{ "a": {} }}"#,
+        deep.replacen('[', "", 1).replacen(']', "", 1)
+    );
+    let err = engine.add_data_json(&deep_object).unwrap_err().to_string();
+    assert!(err.contains("value exceeds maximum depth of 64"), "{err}");
+
+    // Parsing stops at the limit, before the truncated end of the document is reached.
+    let truncated = r#"{ "a": "#.repeat(100);
+    let err = engine.set_input_json(&truncated).unwrap_err().to_string();
+    assert!(err.contains("value exceeds maximum depth of 64"), "{err}");
+
+    // Wide document.
+    let wide = format!(r#"{{ "a": [{}] }}"#, vec!["1"; 10_000].join(","));
+    let err = engine.set_input_json(&wide).unwrap_err().to_string();
+    assert!(
+        err.contains("value exceeds maximum node count of 10000"),
+        "{err}"
+    );
+    let err = engine.add_data_json(&wide).unwrap_err().to_string();
+    assert!(
+        err.contains("value exceeds maximum node count of 10000"),
+        "{err}"
+    );
+
+    // Already constructed values are checked by add_data.
+    let err = engine
+        .add_data(Value::from_json_str(&wide)?)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("value exceeds maximum node count of 10000"),
+        "{err}"
+    );
+
+    let err = engine
+        .patch_data("/a", Value::from_json_str(&wide)?)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("value exceeds maximum node count of 10000"),
+        "{err}"
+    );
+
+    // Patches are checked against the resulting data document.
+    let half = format!("[{}]", vec!["1"; 6_000].join(","));
+    engine.patch_data("/b", Value::from_json_str(&half)?)?;
+    let err = engine
+        .patch_data("/c", Value::from_json_str(&half)?)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("value exceeds maximum node count of 10000"),
+        "{err}"
+    );
+    assert_eq!(engine.get_data()["c"], Value::Undefined);
+    engine.remove_data("/b")?;
+
+    let err = engine
+        .patch_data(&"/x".repeat(65), Value::from(1))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("value exceeds maximum depth of 64"), "{err}");
+
+    // Rejected documents leave the input untouched.
+    assert_eq!(
+        engine.eval_rule("data.test.len".to_string())?,
+        Value::from(3u64)
+    );
+
+    // Removing the limits accepts the wide document.
+    engine.set_value_limits(ValueLimits::default());
+    engine.set_input_json(&wide)?;
+    assert_eq!(
+        engine.eval_rule("data.test.len".to_string())?,
+        Value::from(10_000u64)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn step_budget() -> Result<()> {
+    let mut engine = Engine::new();
+    engine.add_policy(
+        "test.rego".to_string(),
+        r#"
+        package test
+        import rego.v1
+
+        small := count([[x, y] | some x in numbers.range(1, 10); some y in numbers.range(1, 10)])
+        large := count([[x, y] | some x in numbers.range(1, 2000); some y in numbers.range(1, 2000)])
+
+        pair_count(n) := count([[x, y] | some x in numbers.range(1, n); some y in numbers.range(1, n)])
+        via_function := pair_count(2000)
+        "#
+        .to_string(),
+    )?;
+
+    engine.set_max_steps(Some(50_000));
+
+    // Within budget.
+    assert_eq!(
+        engine.eval_rule("data.test.small".to_string())?,
+        Value::from(100u64)
+    );
+
+    // A large cartesian product trips the budget.
+    let err = engine.eval_rule("data.test.large".to_string()).unwrap_err();
+    let budget = err.downcast_ref::<BudgetExceeded>().expect("{err}");
+    assert_eq!(budget.max_steps, 50_000);
+    assert_eq!(budget.file, "test.rego");
+    assert_eq!(budget.line, 6);
+    assert!(
+        err.to_string().contains("evaluation budget exceeded"),
+        "{err}"
+    );
+
+    // Errors inside functions are not swallowed, even with non-strict builtin errors.
+    engine.set_strict_builtin_errors(false);
+    let err = engine
+        .eval_rule("data.test.via_function".to_string())
+        .unwrap_err();
+    assert!(err.downcast_ref::<BudgetExceeded>().is_some(), "{err}");
+
+    // The budget applies per evaluation.
+    for _ in 0..5 {
+        assert_eq!(
+            engine.eval_rule("data.test.small".to_string())?,
+            Value::from(100u64)
+        );
+    }
+
+    // Without a budget, the evaluation completes.
+    engine.set_max_steps(Some(50));
+    assert!(engine.eval_rule("data.test.small".to_string()).is_err());
+    engine.set_max_steps(None);
+    assert_eq!(
+        engine.eval_rule("data.test.small".to_string())?,
+        Value::from(100u64)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn cancellation() -> Result<()> {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    let flag = Arc::new(AtomicBool::new(false));
+
+    // Engine is not Send, so it is created within the evaluating thread.
+    let eval_flag = flag.clone();
+    let handle = std::thread::spawn(move || -> Result<bool> {
+        let mut engine = Engine::new();
+        engine.add_policy(
+            "test.rego".to_string(),
+            r#"
+            package test
+            import rego.v1
+
+            pairs := count([[x, y] | some x in numbers.range(1, 100000); some y in numbers.range(1, 100000)])
+            "#
+            .to_string(),
+        )?;
+        engine.set_cancellation_flag(Some(eval_flag));
+
+        let err = engine.eval_rule("data.test.pairs".to_string()).unwrap_err();
+        Ok(err.downcast_ref::<Cancelled>().is_some())
+    });
+
+    std::thread::sleep(Duration::from_millis(100));
+    let cancelled_at = Instant::now();
+    flag.store(true, Ordering::Relaxed);
+
+    // The flag may be set before evaluation starts on a loaded machine. Either way the
+    // evaluation must be cancelled promptly.
+    let cancelled = handle.join().expect("evaluation thread panicked")?;
+    assert!(cancelled);
+    assert!(cancelled_at.elapsed() < Duration::from_secs(5));
+
+    Ok(())
+}
@@ -565,11 +565,53 @@ fn match_rule_bodies(span: &Span, bodies: &[RuleBody], v: &Value) -> Result<()>
     Ok(())
 }
 
+fn match_annotation_opt(span: &Span, a: &Option<Ref<Annotation>>, v: &Value) -> Result<()> {
+    match (a, v) {
+        (_, Value::Undefined) | (None, Value::Null) => Ok(()),
+        (Some(a), Value::Object(fields)) => {
+            match_span_opt(&a.span, &v["span"])?;
+            let message = || {
+                a.span
+                    .source
+                    .message(a.span.line, a.span.col, "mismatch-error", "annotation")
+            };
+            for (field, expected) in fields.iter() {
+                let actual = match field.as_string()?.as_ref() {
+                    "span" => continue,
+                    "yaml" => Value::from(a.yaml.as_str()),
+                    "scope" => Value::from(a.scope.as_str()),
+                    "title" => a.title.as_deref().map_or(Value::Null, Value::from),
+                    "description" => a.description.as_deref().map_or(Value::Null, Value::from),
+                    "custom" => match &a.custom {
+                        Value::Undefined => Value::Null,
+                        c => c.clone(),
+                    },
+                    "value" => a.value.clone(),
+                    f => bail!("unknown annotation field {f}"),
+                };
+                my_assert_eq!(&actual, expected, "{}", message());
+            }
+            Ok(())
+        }
+        _ => Err(span.source.error(
+            span.line,
+            span.col,
+            &format!("annotation mismatch. expecting {v}"),
+        )),
+    }
+}
+
 fn match_rule(r: &Rule, v: &Value) -> Result<()> {
     match r {
-        Rule::Spec { span, head, bodies } => {
+        Rule::Spec {
+            span,
+            head,
+            bodies,
+            annotation,
+        } => {
             let obj = &v["spec"];
             match_span_opt(span, &obj["span"])?;
+            match_annotation_opt(span, annotation, &obj["annotation"])?;
             match_rule_head(head, &obj["head"])?;
             match_rule_bodies(span, bodies, &obj["bodies"])
         }
@@ -579,9 +621,11 @@ fn match_rule(r: &Rule, v: &Value) -> Result<()> {
             args,
             op,
             value,
+            annotation,
         } => {
             let obj = &v["default"];
             match_span_opt(span, &obj["span"])?;
+            match_annotation_opt(span, annotation, &obj["annotation"])?;
             match_expr(refr, &obj["refr"])?;
             match_vec(span /*dummy*/, args, &obj["args"])?;
             match_assign_op(span, op, &obj["op"])?;
@@ -595,6 +639,7 @@ fn match_package(p: &Package, v: &Value) -> Result<()> {
         return Ok(());
     }
     match_span_opt(&p.span, &v["span"])?;
+    match_annotation_opt(&p.span, &p.annotation, &v["annotation"])?;
     match_expr(&p.refr, &v["refr"])
 }
 
@@ -698,6 +743,12 @@ fn yaml_test_impl(file: &str) -> Result<()> {
 }
 
 fn yaml_test(file: &str) -> Result<()> {
+    // Parsing annotations requires the yaml feature.
+    #[cfg(not(feature = "yaml"))]
+    if file.contains("annotations/") {
+        return Ok(());
+    }
+
     match yaml_test_impl(file) {
         Ok(_) => Ok(()),
         Err(e) => {
@@ -708,6 +759,91 @@ fn yaml_test(file: &str) -> Result<()> {
     }
 }
 
+#[test]
+#[cfg(not(feature = "yaml"))]
+fn annotations_require_yaml() -> Result<()> {
+    let source = Source::from_contents(
+        "case.rego".to_string(),
+        "package test\n\n# METADATA\n# title: Allow\nallow := true\n".to_string(),
+    )?;
+    let error = Parser::new(&source)?.parse().unwrap_err().to_string();
+    assert!(
+        error.contains("parsing annotations requires the `yaml` feature"),
+        "{error}"
+    );
+    Ok(())
+}
+
+#[test]
+fn multiple_errors() -> Result<()> {
+    let source = Source::from_contents(
+        "case.rego".to_string(),
+        r#"package test
+import rego.v1
+
+a := 1 +
+
+b := 2
+
+c if {
+  x := [1, 2
+  x[0] > 0
+}
+
+d contains 5
+"#
+        .to_string(),
+    )?;
+
+    let errors = match Parser::new(&source)?.parse_with_diagnostics() {
+        Ok(_) => bail!("errors not raised by parser"),
+        Err(errors) => errors,
+    };
+    let errors: Vec<(u32, u32, &str)> = errors
+        .iter()
+        .map(|e| (e.span.line, e.span.col, e.message.as_str()))
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            (6, 3, "expecting identifier. Failed to parse rule-ref."),
+            (9, 5, "expecting `}` while parsing set")
+        ]
+    );
+
+    // Only the first error is reported when parsing without diagnostics.
+    let error = Parser::new(&source)?.parse().unwrap_err().to_string();
+    assert!(error.contains("case.rego:6:3"));
+    assert!(!error.contains("case.rego:9:5"));
+
+    Ok(())
+}
+
+#[test]
+fn diagnostics_with_tabs() -> Result<()> {
+    let source = Source::from_contents(
+        "case.rego".to_string(),
+        "package test\n\na := \"é\" +\n\n\tb := 2\n".to_string(),
+    )?;
+
+    let errors = match Parser::new(&source)?.parse_with_diagnostics() {
+        Ok(_) => bail!("errors not raised by parser"),
+        Err(errors) => errors,
+    };
+    assert_eq!(errors.len(), 1);
+
+    // A tab counts as 4 columns but occupies a single byte.
+    let span = &errors[0].span;
+    assert_eq!((span.line, span.col), (5, 7));
+    assert_eq!(span.text(), ":=");
+    assert_eq!(
+        errors[0].message,
+        "expecting identifier. Failed to parse rule-ref."
+    );
+
+    Ok(())
+}
+
 #[test_resources("tests/parser/**/*.yaml")]
 fn run(path: &str) {
     yaml_test(path).unwrap()
@@ -38,14 +38,15 @@ fn non_string_key() -> Result<()> {
     let json = serde_json::to_string_pretty(&obj)?;
     println!("{json}");
 
+    // Keys are emitted in the sorted order of their serialized form.
     let expected = r#"{
-  "null": null,
-  "false": null,
+  "\"<undefined>\"": null,
   "3.141592653589793": null,
-  "[true,null,3.141592653589793]": null,
   "[false,true,3.141592653589793]": null,
-  "{\"null\":null,\"false\":null,\"3.141592653589793\":null,\"[true,null,3.141592653589793]\":null,\"[false,true,3.141592653589793]\":null,\"\\\"<undefined>\\\"\":null}": null,
-  "\"<undefined>\"": null
+  "[true,null,3.141592653589793]": null,
+  "false": null,
+  "null": null,
+  "{\"\\\"<undefined>\\\"\":null,\"3.141592653589793\":null,\"[false,true,3.141592653589793]\":null,\"[true,null,3.141592653589793]\":null,\"false\":null,\"null\":null}": null
 }"#;
 
     assert_eq!(json, expected);
@@ -155,3 +156,142 @@ fn api() -> Result<()> {
     assert!(Value::from(5.6).as_bool_mut().is_err());
     Ok(())
 }
+
+#[test]
+fn json_bytes_and_serde() -> Result<()> {
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Container {
+        name: String,
+        ports: Vec<u16>,
+        privileged: bool,
+        limits: std::collections::BTreeMap<String, f64>,
+        parent: Option<Box<Container>>,
+    }
+
+    let container = Container {
+        name: "app".to_string(),
+        ports: vec![80, 443],
+        privileged: false,
+        limits: [("cpu".to_string(), 0.5)].into_iter().collect(),
+        parent: Some(Box::new(Container {
+            name: "pod".to_string(),
+            ports: vec![],
+            privileged: true,
+            limits: Default::default(),
+            parent: None,
+        })),
+    };
+
+    let value = Value::from_serde(&container)?;
+    assert_eq!(value["name"], Value::from("app"));
+    assert_eq!(value["ports"][1], Value::from(443u64));
+    assert_eq!(value["limits"]["cpu"], Value::from(0.5));
+    assert_eq!(value["parent"]["privileged"], Value::from(true));
+    assert_eq!(value["parent"]["parent"], Value::Null);
+
+    // Round trip via bytes.
+    let bytes = value.to_json_vec()?;
+    assert_eq!(bytes, value.to_json_str()?.into_bytes());
+    assert_eq!(serde_json::from_slice::<Container>(&bytes)?, container);
+    assert_eq!(Value::from_json_slice(&bytes)?, value);
+
+    // Byte slice parsing matches string parsing.
+    let json = r#"{"a": [1, 2.5, "x", null, true], "b": {"c": -3}, "d": 18446744073709551615}"#;
+    assert_eq!(
+        Value::from_json_slice(json.as_bytes())?,
+        Value::from_json_str(json)?
+    );
+    assert_eq!(
+        Value::from_json_slice(b"[1,").unwrap_err().to_string(),
+        Value::from_json_str("[1,").unwrap_err().to_string()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn typed_accessors() -> Result<()> {
+    let v = Value::from_json_str(
+        r#"{
+             "count": -42,
+             "ratio": 0.25,
+             "enabled": true,
+             "name": "regorus",
+             "items": [10, "x"],
+             "nested": { "k": null }
+           }"#,
+    )?;
+
+    // Successful extraction.
+    assert_eq!(v["count"].as_i64()?, -42);
+    assert_eq!(v["count"].as_f64()?, -42.0);
+    assert_eq!(v["ratio"].as_f64()?, 0.25);
+    assert_eq!(*v["enabled"].as_bool()?, true);
+    assert_eq!(v["name"].as_str()?, "regorus");
+    assert_eq!(v["items"].as_array()?.len(), 2);
+    assert_eq!(v["items"][0usize].as_u64()?, 10);
+    assert_eq!(v["items"][1usize].as_str()?, "x");
+    assert_eq!(v["nested"].as_object()?.len(), 1);
+
+    // Type mismatches.
+    assert!(v["count"].as_str().is_err());
+    assert!(v["count"].as_u64().is_err());
+    assert!(v["ratio"].as_i64().is_err());
+    assert!(v["name"].as_f64().is_err());
+    assert!(v["name"].as_bool().is_err());
+    assert!(v["enabled"].as_i64().is_err());
+    assert!(v["items"].as_object().is_err());
+    assert!(v["nested"].as_array().is_err());
+    assert!(Value::Undefined.as_str().is_err());
+
+    // get returns None for missing keys and non-collections.
+    assert_eq!(v.get("name"), Some(&Value::from("regorus")));
+    assert_eq!(v.get("missing"), None);
+    assert_eq!(v.get("nested").and_then(|n| n.get("k")), Some(&Value::Null));
+    assert_eq!(v["items"].get(1usize), Some(&Value::from("x")));
+    assert_eq!(v["items"].get(2usize), None);
+    assert_eq!(v["items"].get("0"), None);
+    assert_eq!(v["name"].get(0usize), None);
+    assert_eq!(v["items"][5usize], Value::Undefined);
+
+    Ok(())
+}
+
+#[test]
+fn set_serialization_order() -> Result<()> {
+    let elements = vec![
+        Value::from("b"),
+        Value::from(10u64),
+        Value::Null,
+        Value::from(true),
+        Value::from("a"),
+        Value::from(2.5),
+        Value::from(false),
+        Value::from(-1i64),
+        Value::from_json_str("[1]")?,
+        Value::from_json_str(r#"{"k": 1}"#)?,
+    ];
+
+    // Insertion order does not affect the serialized order.
+    let forward: std::collections::BTreeSet<Value> = elements.iter().cloned().collect();
+    let backward: std::collections::BTreeSet<Value> = elements.iter().rev().cloned().collect();
+    let expected = r#"[null,false,true,-1,2.5,10,"a","b",[1],{"k":1}]"#;
+    assert_eq!(serde_json::to_string(&Value::from(forward))?, expected);
+    assert_eq!(serde_json::to_string(&Value::from(backward))?, expected);
+
+    // Serialized order matches the order used for comparison.
+    let set = Value::from(
+        elements
+            .iter()
+            .cloned()
+            .collect::<std::collections::BTreeSet<_>>(),
+    );
+    let mut sorted = elements.clone();
+    sorted.sort();
+    assert_eq!(
+        serde_json::to_string(&set)?,
+        serde_json::to_string(&Value::from(sorted))?
+    );
+
+    Ok(())
+}